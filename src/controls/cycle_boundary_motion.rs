@@ -0,0 +1,18 @@
+use bevy::prelude::*;
+
+use crate::kinetics::bounds::kinematic::BoundaryMotion;
+
+pub fn cycle_boundary_motion(
+    mut boundaries_q: Query<&mut BoundaryMotion>,
+    keys: Res<ButtonInput<KeyCode>>,
+) {
+    if keys.just_pressed(KeyCode::KeyM) {
+        for mut motion in boundaries_q.iter_mut() {
+            *motion = match *motion {
+                BoundaryMotion::Static => BoundaryMotion::slosh(),
+                BoundaryMotion::Function(_) => BoundaryMotion::tilt(),
+                BoundaryMotion::Keyframes(_) => BoundaryMotion::Static,
+            };
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use bevy::prelude::*;
+
+use crate::kinetics::collisions::contact_solver::{ContactAccumulation, ContactSolverSettings};
+
+pub fn cycle_contact_accumulation(
+    mut contact_solver_settings: ResMut<ContactSolverSettings>,
+    keys: Res<ButtonInput<KeyCode>>,
+) {
+    if keys.just_pressed(KeyCode::KeyJ) {
+        contact_solver_settings.accumulation = match contact_solver_settings.accumulation {
+            ContactAccumulation::Sequential => ContactAccumulation::Jacobi,
+            ContactAccumulation::Jacobi => ContactAccumulation::Sequential,
+        };
+    }
+}
@@ -0,0 +1,14 @@
+use bevy::prelude::*;
+
+use crate::kinetics::bounds::Container;
+
+pub fn cycle_container(mut container: ResMut<Container>, keys: Res<ButtonInput<KeyCode>>) {
+    if keys.just_pressed(KeyCode::KeyB) {
+        *container = match *container {
+            Container::Box => Container::Funnel,
+            Container::Funnel => Container::UTube,
+            Container::UTube => Container::Bowl,
+            Container::Bowl => Container::Box,
+        };
+    }
+}
@@ -0,0 +1,14 @@
+use bevy::prelude::*;
+
+use crate::kinetics::integrator::Integrator;
+
+pub fn cycle_integrator(mut integrator: ResMut<Integrator>, keys: Res<ButtonInput<KeyCode>>) {
+    if keys.just_pressed(KeyCode::KeyI) {
+        *integrator = match *integrator {
+            Integrator::SemiImplicitEuler => Integrator::VelocityVerlet,
+            Integrator::VelocityVerlet => Integrator::Leapfrog,
+            Integrator::Leapfrog => Integrator::Rk4,
+            Integrator::Rk4 => Integrator::SemiImplicitEuler,
+        };
+    }
+}
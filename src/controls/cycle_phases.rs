@@ -0,0 +1,13 @@
+use bevy::prelude::*;
+
+use crate::fluids::kinds::Phases;
+
+pub fn cycle_phases(mut phases: ResMut<Phases>, keys: Res<ButtonInput<KeyCode>>) {
+    if keys.just_pressed(KeyCode::KeyK) {
+        *phases = match *phases {
+            Phases::Water => Phases::OilAndWater,
+            Phases::OilAndWater => Phases::RayleighTaylor,
+            Phases::RayleighTaylor => Phases::Water,
+        };
+    }
+}
@@ -0,0 +1,58 @@
+use bevy::prelude::*;
+
+use crate::kinetics::{
+    forces::{ForceSource, Forces},
+    mass::Mass,
+};
+
+pub fn toggle_force_overlay(
+    mut force_overlay_toggled: ResMut<ForceOverlayToggled>,
+    keys: Res<ButtonInput<KeyCode>>,
+) {
+    if keys.just_pressed(KeyCode::KeyV) {
+        force_overlay_toggled.0 = !force_overlay_toggled.0;
+    }
+}
+
+pub fn is_force_overlay_toggled(force_overlay_toggled: Res<ForceOverlayToggled>) -> bool {
+    force_overlay_toggled.0
+}
+
+#[derive(Resource)]
+pub struct ForceOverlayToggled(pub bool);
+
+/// Length, in pixels, of the arrow of an acceleration of 1 m/s².
+const PIXELS_PER_ACCELERATION: f32 = 1.5;
+
+/// Draws, from every particle, one arrow per source of the forces acting on
+/// it at the last step, as the acceleration it gives, so that the particles
+/// of different masses compare.
+pub fn draw_force_overlay(mut gizmos: Gizmos, particles_q: Query<(&Transform, &Mass, &Forces)>) {
+    for (transform, Mass(mass), forces) in particles_q.iter() {
+        let center = transform.translation.xy();
+        for (source, force) in forces.iter() {
+            if force == Vec2::ZERO {
+                continue;
+            }
+            gizmos
+                .arrow_2d(
+                    center,
+                    center + force / mass * PIXELS_PER_ACCELERATION,
+                    color_of(source),
+                )
+                .with_tip_length(2.);
+        }
+    }
+}
+
+fn color_of(source: ForceSource) -> Color {
+    match source {
+        ForceSource::Gravity => Color::hsl(270., 0.8, 0.7),
+        ForceSource::Collision => Color::hsl(0., 0.8, 0.6),
+        ForceSource::Boundary => Color::hsl(40., 0.9, 0.6),
+        ForceSource::Pressure => Color::hsl(200., 0.8, 0.6),
+        ForceSource::Viscosity => Color::hsl(120., 0.7, 0.5),
+        ForceSource::SurfaceTension => Color::hsl(300., 0.8, 0.6),
+        ForceSource::User => Color::WHITE,
+    }
+}
@@ -1,4 +1,17 @@
+pub mod cycle_boundary_motion;
+pub mod cycle_contact_accumulation;
+pub mod cycle_container;
+pub mod cycle_integrator;
+pub mod cycle_phases;
+pub mod force_overlay;
+pub mod probe;
+pub mod toggle_attraction;
+pub mod toggle_continuous_collisions;
+pub mod toggle_force_fields;
 pub mod toggle_gravity;
+pub mod toggle_limiter;
+pub mod toggle_obstacles;
+pub mod toggle_transfer;
 
 use bevy::prelude::*;
 
@@ -6,11 +19,38 @@ pub struct ControlsPlugin;
 
 impl Plugin for ControlsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, init_controls)
-            .add_systems(Update, toggle_gravity::toggle_gravity);
+        app.add_systems(Startup, init_controls).add_systems(
+            Update,
+            (
+                toggle_gravity::toggle_gravity,
+                toggle_limiter::toggle_limiter,
+                toggle_attraction::toggle_attraction,
+                toggle_continuous_collisions::toggle_continuous_collisions,
+                toggle_force_fields::toggle_force_fields,
+                toggle_obstacles::toggle_obstacles,
+                toggle_transfer::toggle_transfer,
+                cycle_integrator::cycle_integrator,
+                cycle_container::cycle_container,
+                cycle_contact_accumulation::cycle_contact_accumulation,
+                cycle_boundary_motion::cycle_boundary_motion,
+                cycle_phases::cycle_phases,
+                probe::toggle_probe,
+                force_overlay::toggle_force_overlay,
+                force_overlay::draw_force_overlay.run_if(force_overlay::is_force_overlay_toggled),
+                probe::draw_probe.run_if(probe::is_probe_toggled),
+            ),
+        );
     }
 }
 
 fn init_controls(mut commands: Commands) {
     commands.insert_resource(toggle_gravity::GravityToggled(true));
+    commands.insert_resource(toggle_attraction::AttractionToggled(false));
+    commands.insert_resource(toggle_continuous_collisions::ContinuousCollisionsToggled(
+        true,
+    ));
+    commands.insert_resource(toggle_force_fields::ForceFieldsToggled(false));
+    commands.insert_resource(toggle_obstacles::ObstaclesToggled(false));
+    commands.insert_resource(probe::ProbeToggled(false));
+    commands.insert_resource(force_overlay::ForceOverlayToggled(false));
 }
@@ -0,0 +1,87 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::kinetics::collisions::position_hashing::query::FluidQuery;
+
+pub fn toggle_probe(mut probe_toggled: ResMut<ProbeToggled>, keys: Res<ButtonInput<KeyCode>>) {
+    if keys.just_pressed(KeyCode::KeyP) {
+        probe_toggled.0 = !probe_toggled.0;
+    }
+}
+
+pub fn is_probe_toggled(probe_toggled: Res<ProbeToggled>) -> bool {
+    probe_toggled.0
+}
+
+#[derive(Resource)]
+pub struct ProbeToggled(pub bool);
+
+const PROBE_RADIUS: f32 = 30.;
+const PROBE_NEIGHBOURS: usize = 5;
+const PROBE_RAY_LENGTH: f32 = 150.;
+
+/// Shows what the `FluidQuery` finds around the cursor: the particles within
+/// a radius, the nearest ones, the ones hit by a ray going down, and the ones
+/// in the rectangle dragged with the left mouse button.
+pub fn draw_probe(
+    fluid_query: FluidQuery,
+    mut gizmos: Gizmos,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    particles_q: Query<&Transform>,
+    mut drag_start: Local<Option<Vec2>>,
+) {
+    let (Ok(window), Ok((camera, camera_transform))) =
+        (window_q.get_single(), camera_q.get_single())
+    else {
+        return;
+    };
+    let Some(cursor) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+    else {
+        return;
+    };
+    let center_of = |entity: Entity| {
+        particles_q
+            .get(entity)
+            .map_or(Vec2::ZERO, |transform| transform.translation.xy())
+    };
+
+    gizmos.circle_2d(cursor, PROBE_RADIUS, Color::hsla(120., 0.8, 0.5, 0.5));
+    for (entity, _) in fluid_query.within_radius(cursor, PROBE_RADIUS) {
+        gizmos.circle_2d(center_of(entity), 2., Color::hsl(120., 0.8, 0.5));
+    }
+
+    for (entity, _) in fluid_query.nearest_k(cursor, PROBE_NEIGHBOURS) {
+        gizmos.line_2d(cursor, center_of(entity), Color::WHITE);
+    }
+
+    let ray = Ray2d::new(cursor, Dir2::NEG_Y);
+    gizmos.line_2d(
+        cursor,
+        ray.get_point(PROBE_RAY_LENGTH),
+        Color::hsla(0., 0.8, 0.5, 0.5),
+    );
+    if let Some((_, distance)) = fluid_query.raycast(ray, PROBE_RAY_LENGTH).first() {
+        gizmos.cross_2d(
+            Isometry2d::from_translation(ray.get_point(*distance)),
+            6.,
+            Color::hsl(0., 0.8, 0.5),
+        );
+    }
+
+    if mouse_buttons.just_pressed(MouseButton::Left) {
+        *drag_start = Some(cursor);
+    }
+    if !mouse_buttons.pressed(MouseButton::Left) {
+        *drag_start = None;
+    }
+    if let Some(drag_start) = *drag_start {
+        let rect = Rect::from_corners(drag_start, cursor);
+        gizmos.rect_2d(rect.center(), rect.size(), Color::hsla(210., 0.8, 0.5, 0.5));
+        for (entity, _) in fluid_query.in_rect(rect) {
+            gizmos.circle_2d(center_of(entity), 2., Color::hsl(210., 0.8, 0.5));
+        }
+    }
+}
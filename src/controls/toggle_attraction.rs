@@ -0,0 +1,17 @@
+use bevy::prelude::*;
+
+pub fn toggle_attraction(
+    mut attraction_toggled: ResMut<AttractionToggled>,
+    keys: Res<ButtonInput<KeyCode>>,
+) {
+    if keys.just_pressed(KeyCode::KeyA) {
+        attraction_toggled.0 = !attraction_toggled.0;
+    }
+}
+
+pub fn is_attraction_toggled(attraction_toggled: Res<AttractionToggled>) -> bool {
+    attraction_toggled.0
+}
+
+#[derive(Resource)]
+pub struct AttractionToggled(pub bool);
@@ -0,0 +1,13 @@
+use bevy::prelude::*;
+
+pub fn toggle_continuous_collisions(
+    mut continuous_collisions_toggled: ResMut<ContinuousCollisionsToggled>,
+    keys: Res<ButtonInput<KeyCode>>,
+) {
+    if keys.just_pressed(KeyCode::KeyC) {
+        continuous_collisions_toggled.0 = !continuous_collisions_toggled.0;
+    }
+}
+
+#[derive(Resource)]
+pub struct ContinuousCollisionsToggled(pub bool);
@@ -0,0 +1,17 @@
+use bevy::prelude::*;
+
+pub fn toggle_force_fields(
+    mut force_fields_toggled: ResMut<ForceFieldsToggled>,
+    keys: Res<ButtonInput<KeyCode>>,
+) {
+    if keys.just_pressed(KeyCode::KeyF) {
+        force_fields_toggled.0 = !force_fields_toggled.0;
+    }
+}
+
+pub fn is_force_fields_toggled(force_fields_toggled: Res<ForceFieldsToggled>) -> bool {
+    force_fields_toggled.0
+}
+
+#[derive(Resource)]
+pub struct ForceFieldsToggled(pub bool);
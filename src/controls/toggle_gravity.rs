@@ -6,5 +6,9 @@ pub fn toggle_gravity(mut gravity_toggled: ResMut<GravityToggled>, keys: Res<But
     }
 }
 
+pub fn is_gravity_toggled(gravity_toggled: Res<GravityToggled>) -> bool {
+    gravity_toggled.0
+}
+
 #[derive(Resource)]
 pub struct GravityToggled(pub bool);
@@ -0,0 +1,9 @@
+use bevy::prelude::*;
+
+use crate::kinetics::limiter::MotionLimits;
+
+pub fn toggle_limiter(mut motion_limits: ResMut<MotionLimits>, keys: Res<ButtonInput<KeyCode>>) {
+    if keys.just_pressed(KeyCode::KeyL) {
+        motion_limits.enabled = !motion_limits.enabled;
+    }
+}
@@ -0,0 +1,17 @@
+use bevy::prelude::*;
+
+pub fn toggle_obstacles(
+    mut obstacles_toggled: ResMut<ObstaclesToggled>,
+    keys: Res<ButtonInput<KeyCode>>,
+) {
+    if keys.just_pressed(KeyCode::KeyO) {
+        obstacles_toggled.0 = !obstacles_toggled.0;
+    }
+}
+
+pub fn is_obstacles_toggled(obstacles_toggled: Res<ObstaclesToggled>) -> bool {
+    obstacles_toggled.0
+}
+
+#[derive(Resource)]
+pub struct ObstaclesToggled(pub bool);
@@ -0,0 +1,12 @@
+use bevy::prelude::*;
+
+use crate::fluids::flip::{FlipSettings, Transfer};
+
+pub fn toggle_transfer(mut flip_settings: ResMut<FlipSettings>, keys: Res<ButtonInput<KeyCode>>) {
+    if keys.just_pressed(KeyCode::KeyT) {
+        flip_settings.transfer = match flip_settings.transfer {
+            Transfer::PicFlip { .. } => Transfer::Apic,
+            Transfer::Apic => FlipSettings::default().transfer,
+        };
+    }
+}
@@ -1,49 +1,75 @@
 use crate::{
-    fluids::particle::FluidParticle,
+    fluids::{
+        density::Density,
+        flip::AffineVelocity,
+        kinds::{select_fluid_kinds, FluidKind, FluidKinds, FluidMaterial},
+        particle::FluidParticle,
+        position_based::PredictedPosition,
+        pressure::Pressure,
+    },
     kinetics::{
         acceleration::Acceleration,
         bounds::{MAX_X, MAX_Y, MIN_X, MIN_Y},
         forces::Forces,
         mass::Mass,
         velocity::Velocity,
+        Solver,
     },
 };
 use bevy::prelude::*;
 use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::ops::Range;
 
 pub struct DrawPlugin;
 
 impl Plugin for DrawPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, draw_circle)
-            .insert_resource(SpawnTimer(Timer::from_seconds(0.1, TimerMode::Repeating)));
-        // .add_systems(
-        //     Update,
-        //     continuously_spawn.run_if(on_timer(Duration::from_millis(10))),
-        // );
+        app.add_systems(
+            Update,
+            draw_circle
+                .run_if(
+                    not(resource_equals(Solver::StableFluids)).and(resource_changed::<FluidKinds>),
+                )
+                // The particles are replaced in the same frame as the kinds,
+                // so none of them is left with a kind that went away.
+                .after(select_fluid_kinds),
+        );
     }
 }
 
+/// Fills the domain with particles, replacing those there were. Each kind
+/// gets a horizontal layer, stacked from the bottom in the order of the
+/// `FluidKinds`.
 fn draw_circle(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    fluid_kinds: Res<FluidKinds>,
+    particles_q: Query<Entity, With<FluidParticle>>,
 ) {
+    for entity in particles_q.iter() {
+        commands.entity(entity).despawn();
+    }
     let mut rng = StdRng::seed_from_u64(40);
 
-    let p1 = FluidParticle {
-        radius: 3.,
-        restitution_coeff: 0.97,
-    };
-    for _ in 1..3000 {
-        spawn_random_particle(
-            &mut commands,
-            &mut meshes,
-            &mut materials,
-            &mut rng,
-            p1,
-            Mass(1.),
-        );
+    let layers = fluid_kinds.iter().count();
+    // Layers start almost still, so that they stay apart until the
+    // instabilities between them grow.
+    let speed = if layers > 1 { 0.5 } else { 5. };
+    let layer_height = (MAX_Y - MIN_Y) / layers as f32;
+    for (layer, (kind, material)) in fluid_kinds.iter().enumerate() {
+        let min_y = MIN_Y + layer as f32 * layer_height;
+        for _ in 0..2999 / layers {
+            spawn_random_particle(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &mut rng,
+                (kind, material),
+                min_y..min_y + layer_height,
+                speed,
+            );
+        }
     }
 }
 
@@ -52,45 +78,48 @@ fn spawn_random_particle(
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<ColorMaterial>>,
     rng: &mut StdRng,
-    p1: FluidParticle,
+    (kind, material): (FluidKind, &FluidMaterial),
+    heights: Range<f32>,
+    speed: f32,
+) {
+    let (particle, mass) = material.particle(3.);
+    let color = material
+        .color
+        .unwrap_or_else(|| Color::hsl(rng.gen_range(0.0..360.), 0.95, 0.7));
+    let position = Vec2::new(rng.gen_range(MIN_X..MAX_X), rng.gen_range(heights));
+    let velocity = Vec2::new(rng.gen_range(-speed..speed), rng.gen_range(-speed..speed));
+    spawn_particle(
+        commands, meshes, materials, particle, kind, mass, position, velocity, color,
+    );
+}
+
+/// Spawns a particle at `position`, in pixels, moving at `velocity`, in m/s,
+/// with everything every solver needs.
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_particle(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    particle: FluidParticle,
+    kind: FluidKind,
     mass: Mass,
+    position: Vec2,
+    velocity: Vec2,
+    color: Color,
 ) {
     commands.spawn((
-        p1,
-        Mesh2d(meshes.add(p1)),
-        MeshMaterial2d(materials.add(Color::hsl(rng.gen_range(0.0..360.), 0.95, 0.7))),
-        Transform::from_xyz(
-            rng.gen_range(MIN_X..MAX_X),
-            rng.gen_range(MIN_Y..MAX_Y),
-            0.,
-        ),
-        // Velocity(Vec2::ZERO),
-        Velocity(Vec2::new(rng.gen_range(-5.0..5.), rng.gen_range(-5.0..5.))),
+        particle,
+        kind,
+        Mesh2d(meshes.add(particle)),
+        MeshMaterial2d(materials.add(color)),
+        Transform::from_translation(position.extend(0.)),
+        Velocity(velocity),
         Acceleration(Vec2::new(0., 0.)),
         mass,
-        Forces(vec![]),
+        Forces::default(),
+        Density::default(),
+        Pressure::default(),
+        PredictedPosition::default(),
+        AffineVelocity::default(),
     ));
 }
-
-fn continuously_spawn(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
-    let mut rng = StdRng::seed_from_u64(40);
-    let p1 = FluidParticle {
-        radius: 3.,
-        restitution_coeff: 0.95,
-    };
-    spawn_random_particle(
-        &mut commands,
-        &mut meshes,
-        &mut materials,
-        &mut rng,
-        p1,
-        Mass(1.),
-    );
-}
-
-#[derive(Resource)]
-struct SpawnTimer(Timer);
@@ -0,0 +1,46 @@
+use bevy::prelude::*;
+
+use super::{kernels, particle::FluidParticle, SphSettings};
+use crate::kinetics::{
+    bounds::periodic::Periodicity, collisions::position_hashing::PositionHashMap, mass::Mass,
+    velocity::PIXELS_PER_METER,
+};
+
+/// Mass density of a particle: its own mass times the number of particles
+/// around it, so that two phases of different densities keep their own at
+/// their interface instead of averaging across it.
+#[derive(Component, Clone, Copy, Default)]
+pub struct Density(pub f32);
+
+impl Density {
+    /// Amount of particles per unit of volume around a particle of `mass`.
+    pub fn number_density(&self, mass: f32) -> f32 {
+        self.0 / mass
+    }
+}
+
+pub fn calculate_densities(
+    sph_settings: Res<SphSettings>,
+    position_hash_map: Res<PositionHashMap>,
+    periodicity: Res<Periodicity>,
+    mut particles_q: Query<(&Transform, &Mass, &mut Density), With<FluidParticle>>,
+    neighbours_q: Query<&Transform, With<FluidParticle>>,
+) {
+    let smoothing_radius = sph_settings.smoothing_radius_in_meters();
+    particles_q
+        .par_iter_mut()
+        .for_each(|(transform, Mass(mass), mut density)| {
+            let center = transform.translation.xy();
+            let number_density: f32 = position_hash_map
+                .entities_near(center, sph_settings.smoothing_radius)
+                .into_iter()
+                .filter_map(|neighbour| neighbours_q.get(neighbour).ok())
+                .map(|neighbour_transform| {
+                    let offset = periodicity.offset(neighbour_transform.translation.xy(), center)
+                        / PIXELS_PER_METER;
+                    kernels::poly6(offset.length_squared(), smoothing_radius)
+                })
+                .sum();
+            density.0 = mass * number_density;
+        });
+}
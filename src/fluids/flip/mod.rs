@@ -0,0 +1,224 @@
+mod tests;
+
+use bevy::prelude::*;
+
+use super::{
+    particle::FluidParticle,
+    stable_fluids::{mac_grid::MacGrid, projection::project_velocities, to_grid_position},
+};
+use crate::kinetics::{
+    bounds::{MAX_X, MAX_Y, MIN_X, MIN_Y},
+    mass::Mass,
+    velocity::{Velocity, PIXELS_PER_METER},
+};
+
+/// Parameters of the hybrid particle-grid solver.
+#[derive(Resource, Clone, Copy)]
+pub struct FlipSettings {
+    /// Side of a grid cell, in pixels.
+    pub cell_size: f32,
+    pub transfer: Transfer,
+    pub pressure_iterations: usize,
+}
+
+impl Default for FlipSettings {
+    fn default() -> Self {
+        FlipSettings {
+            cell_size: 8.,
+            transfer: Transfer::PicFlip { flip_ratio: 0.95 },
+            pressure_iterations: 40,
+        }
+    }
+}
+
+/// How velocities are carried from the grid back to the particles.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Transfer {
+    /// Blend of PIC (`flip_ratio = 0`), which is stable but dissipative, and
+    /// FLIP (`flip_ratio = 1`), which keeps detail but is noisy.
+    PicFlip { flip_ratio: f32 },
+    /// Affine Particle-In-Cell: every particle also carries the local velocity
+    /// gradient, which keeps rotation without the FLIP noise.
+    Apic,
+}
+
+/// Velocity gradient carried by a particle when using `Transfer::Apic`.
+#[derive(Component, Clone, Copy, Default)]
+pub struct AffineVelocity(pub Mat2);
+
+#[derive(Resource)]
+pub struct FlipGrid {
+    pub grid: MacGrid,
+    previous_u: Vec<f32>,
+    previous_v: Vec<f32>,
+    fluid_cells: Vec<bool>,
+}
+
+pub fn init_flip_grid(mut commands: Commands, flip_settings: Res<FlipSettings>) {
+    let width = ((MAX_X - MIN_X) / flip_settings.cell_size) as usize;
+    let height = ((MAX_Y - MIN_Y) / flip_settings.cell_size) as usize;
+    let grid = MacGrid::new(width, height, flip_settings.cell_size / PIXELS_PER_METER);
+    commands.insert_resource(FlipGrid {
+        previous_u: grid.u.clone(),
+        previous_v: grid.v.clone(),
+        fluid_cells: vec![false; width * height],
+        grid,
+    });
+}
+
+pub fn transfer_particles_to_grid(
+    mut flip_grid: ResMut<FlipGrid>,
+    flip_settings: Res<FlipSettings>,
+    particles_q: Query<(&Transform, &Velocity, &Mass, &AffineVelocity), With<FluidParticle>>,
+) {
+    let FlipGrid {
+        grid, fluid_cells, ..
+    } = flip_grid.as_mut();
+    let (width, height, cell_size) = (grid.width, grid.height, grid.cell_size);
+    let mut u_momentum = vec![0.; grid.u.len()];
+    let mut u_weight = vec![0.; grid.u.len()];
+    let mut v_momentum = vec![0.; grid.v.len()];
+    let mut v_weight = vec![0.; grid.v.len()];
+    fluid_cells.fill(false);
+
+    for (transform, Velocity(velocity), Mass(mass), AffineVelocity(affine_velocity)) in
+        particles_q.iter()
+    {
+        let position = to_grid_position(transform.translation.xy());
+        let affine_velocity = match flip_settings.transfer {
+            Transfer::Apic => *affine_velocity,
+            Transfer::PicFlip { .. } => Mat2::ZERO,
+        };
+
+        let (cell_x, cell_y) = (
+            ((position.x / cell_size) as usize).min(width - 1),
+            ((position.y / cell_size) as usize).min(height - 1),
+        );
+        fluid_cells[cell_x + cell_y * width] = true;
+
+        for (idx, weight, _) in
+            stencil(position / cell_size - Vec2::new(0., 0.5), width + 1, height)
+        {
+            let face = grid.u_position(idx % (width + 1), idx / (width + 1));
+            let momentum = velocity.x + (affine_velocity * (face - position)).x;
+            u_momentum[idx] += weight * mass * momentum;
+            u_weight[idx] += weight * mass;
+        }
+        for (idx, weight, _) in
+            stencil(position / cell_size - Vec2::new(0.5, 0.), width, height + 1)
+        {
+            let face = grid.v_position(idx % width, idx / width);
+            let momentum = velocity.y + (affine_velocity * (face - position)).y;
+            v_momentum[idx] += weight * mass * momentum;
+            v_weight[idx] += weight * mass;
+        }
+    }
+
+    for (idx, u) in grid.u.iter_mut().enumerate() {
+        *u = if u_weight[idx] > 0. {
+            u_momentum[idx] / u_weight[idx]
+        } else {
+            0.
+        };
+    }
+    for (idx, v) in grid.v.iter_mut().enumerate() {
+        *v = if v_weight[idx] > 0. {
+            v_momentum[idx] / v_weight[idx]
+        } else {
+            0.
+        };
+    }
+    flip_grid.previous_u = flip_grid.grid.u.clone();
+    flip_grid.previous_v = flip_grid.grid.v.clone();
+}
+
+pub fn solve_pressure(
+    time: Res<Time>,
+    flip_settings: Res<FlipSettings>,
+    mut flip_grid: ResMut<FlipGrid>,
+) {
+    let FlipGrid {
+        grid, fluid_cells, ..
+    } = flip_grid.as_mut();
+    project_velocities(
+        grid,
+        time.delta().as_secs_f32(),
+        flip_settings.pressure_iterations,
+        fluid_cells,
+    );
+}
+
+pub fn transfer_grid_to_particles(
+    flip_grid: Res<FlipGrid>,
+    flip_settings: Res<FlipSettings>,
+    mut particles_q: Query<(&Transform, &mut Velocity, &mut AffineVelocity), With<FluidParticle>>,
+) {
+    let grid = &flip_grid.grid;
+    let (width, height, cell_size) = (grid.width, grid.height, grid.cell_size);
+
+    particles_q
+        .par_iter_mut()
+        .for_each(|(transform, mut velocity, mut affine_velocity)| {
+            let position = to_grid_position(transform.translation.xy());
+            let grid_velocity = grid.velocity_at(position);
+
+            match flip_settings.transfer {
+                Transfer::PicFlip { flip_ratio } => {
+                    let velocity_change = grid_velocity
+                        - Vec2::new(
+                            grid.sample_u(&flip_grid.previous_u, position),
+                            grid.sample_v(&flip_grid.previous_v, position),
+                        );
+                    velocity.0 = flip_ratio * (velocity.0 + velocity_change)
+                        + (1. - flip_ratio) * grid_velocity;
+                }
+                Transfer::Apic => {
+                    let u_gradient: Vec2 =
+                        stencil(position / cell_size - Vec2::new(0., 0.5), width + 1, height)
+                            .iter()
+                            .map(|(idx, _, weight_gradient)| grid.u[*idx] * *weight_gradient)
+                            .sum::<Vec2>()
+                            / cell_size;
+                    let v_gradient: Vec2 =
+                        stencil(position / cell_size - Vec2::new(0.5, 0.), width, height + 1)
+                            .iter()
+                            .map(|(idx, _, weight_gradient)| grid.v[*idx] * *weight_gradient)
+                            .sum::<Vec2>()
+                            / cell_size;
+                    velocity.0 = grid_velocity;
+                    affine_velocity.0 = Mat2::from_cols(
+                        Vec2::new(u_gradient.x, v_gradient.x),
+                        Vec2::new(u_gradient.y, v_gradient.y),
+                    );
+                }
+            }
+        });
+}
+
+/// Bilinear interpolation stencil of `grid_position` (in sample units) on a
+/// `columns * rows` array: the sample indices, their weights and the
+/// gradients of the weights in sample units.
+fn stencil(grid_position: Vec2, columns: usize, rows: usize) -> [(usize, f32, Vec2); 4] {
+    let x = grid_position.x.clamp(0., (columns - 1) as f32);
+    let y = grid_position.y.clamp(0., (rows - 1) as f32);
+    let (i, j) = (
+        (x.floor() as usize).min(columns - 2),
+        (y.floor() as usize).min(rows - 2),
+    );
+    let (tx, ty) = (x - i as f32, y - j as f32);
+
+    [
+        (
+            i + j * columns,
+            (1. - tx) * (1. - ty),
+            Vec2::new(-(1. - ty), -(1. - tx)),
+        ),
+        (i + 1 + j * columns, tx * (1. - ty), Vec2::new(1. - ty, -tx)),
+        (
+            i + (j + 1) * columns,
+            (1. - tx) * ty,
+            Vec2::new(-ty, 1. - tx),
+        ),
+        (i + 1 + (j + 1) * columns, tx * ty, Vec2::new(ty, tx)),
+    ]
+}
@@ -0,0 +1,152 @@
+#[cfg(test)]
+mod flip_tests {
+    use bevy::tasks::{ComputeTaskPool, TaskPool};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::super::*;
+
+    /// Particles two per cell side over the middle of the grid, away from
+    /// its edges, with the velocity and velocity gradient given by `field`.
+    fn world_with_particles(
+        transfer: Transfer,
+        mut field: impl FnMut(Vec2) -> (Vec2, Mat2),
+    ) -> (World, Vec<Entity>) {
+        ComputeTaskPool::get_or_init(TaskPool::default);
+        let mut world = World::new();
+        world.insert_resource(FlipSettings {
+            transfer,
+            ..default()
+        });
+        world.run_system_cached(init_flip_grid).unwrap();
+        world.flush();
+
+        let particles = (-20..20)
+            .flat_map(|i| (-20..20).map(move |j| Vec2::new(i as f32, j as f32) * 4. + 2.))
+            .map(|position| {
+                let (velocity, affine_velocity) = field(position);
+                world
+                    .spawn((
+                        FluidParticle {
+                            radius: 2.,
+                            restitution_coeff: 1.,
+                            friction_coeff: 0.,
+                        },
+                        Transform::from_translation(position.extend(0.)),
+                        Velocity(velocity),
+                        Mass(1.),
+                        AffineVelocity(affine_velocity),
+                    ))
+                    .id()
+            })
+            .collect();
+        (world, particles)
+    }
+
+    fn transfer(world: &mut World) {
+        world.run_system_cached(transfer_particles_to_grid).unwrap();
+        world.run_system_cached(transfer_grid_to_particles).unwrap();
+    }
+
+    fn velocity(world: &World, particle: Entity) -> Vec2 {
+        world.get::<Velocity>(particle).unwrap().0
+    }
+
+    fn momentum(world: &World, particles: &[Entity]) -> Vec2 {
+        particles
+            .iter()
+            .map(|&particle| world.get::<Mass>(particle).unwrap().0 * velocity(world, particle))
+            .sum()
+    }
+
+    #[test]
+    fn pure_pic_takes_the_grid_velocity() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let (mut world, particles) =
+            world_with_particles(Transfer::PicFlip { flip_ratio: 0. }, |_| {
+                (
+                    Vec2::new(rng.gen_range(-1.0..1.), rng.gen_range(-1.0..1.)),
+                    Mat2::ZERO,
+                )
+            });
+        world.run_system_cached(transfer_particles_to_grid).unwrap();
+        // The grid changes between the transfers, as the pressure solve would.
+        let mut flip_grid = world.resource_mut::<FlipGrid>();
+        flip_grid.grid.u.iter_mut().for_each(|u| *u += 1.);
+        flip_grid.grid.v.iter_mut().for_each(|v| *v -= 2.);
+        world.run_system_cached(transfer_grid_to_particles).unwrap();
+
+        let grid = &world.resource::<FlipGrid>().grid;
+        for &particle in &particles {
+            let position = world.get::<Transform>(particle).unwrap().translation.xy();
+            assert_eq!(
+                velocity(&world, particle),
+                grid.velocity_at(to_grid_position(position))
+            );
+        }
+    }
+
+    #[test]
+    fn pure_flip_keeps_a_uniform_velocity() {
+        let uniform = Vec2::new(1.5, -0.5);
+        let (mut world, particles) =
+            world_with_particles(Transfer::PicFlip { flip_ratio: 1. }, |_| {
+                (uniform, Mat2::ZERO)
+            });
+        transfer(&mut world);
+
+        for &particle in &particles {
+            let velocity = velocity(&world, particle);
+            assert!(velocity.abs_diff_eq(uniform, 1e-5), "{velocity}");
+        }
+    }
+
+    #[test]
+    fn apic_reproduces_a_linear_velocity_field() {
+        let gradient = Mat2::from_cols(Vec2::new(0.3, -0.2), Vec2::new(0.5, 0.1));
+        let offset = Vec2::new(-1., 2.);
+        let (mut world, particles) = world_with_particles(Transfer::Apic, |position| {
+            (gradient * to_grid_position(position) + offset, gradient)
+        });
+        transfer(&mut world);
+
+        for &particle in &particles {
+            let position = world.get::<Transform>(particle).unwrap().translation.xy();
+            // The faces around the outermost particles are only partly covered.
+            if position.abs().max_element() > 70. {
+                continue;
+            }
+            let expected = gradient * to_grid_position(position) + offset;
+            let velocity = velocity(&world, particle);
+            assert!(
+                velocity.abs_diff_eq(expected, 1e-4),
+                "{velocity} {expected}"
+            );
+            let affine_velocity = world.get::<AffineVelocity>(particle).unwrap().0;
+            assert!(
+                affine_velocity.abs_diff_eq(gradient, 1e-3),
+                "{affine_velocity}"
+            );
+        }
+    }
+
+    #[test]
+    fn transfers_conserve_the_momentum() {
+        for transfer_kind in [Transfer::PicFlip { flip_ratio: 0. }, Transfer::Apic] {
+            let mut rng = StdRng::seed_from_u64(1);
+            let (mut world, particles) = world_with_particles(transfer_kind, |_| {
+                (
+                    Vec2::new(rng.gen_range(-1.0..1.), rng.gen_range(-1.0..1.)),
+                    Mat2::from_cols_array(&[0.; 4].map(|_| rng.gen_range(-1.0..1.))),
+                )
+            });
+            let initial_momentum = momentum(&world, &particles);
+            transfer(&mut world);
+
+            let momentum = momentum(&world, &particles);
+            assert!(
+                momentum.abs_diff_eq(initial_momentum, 1e-2),
+                "{transfer_kind:?}: {momentum} {initial_momentum}"
+            );
+        }
+    }
+}
@@ -0,0 +1,33 @@
+mod tests;
+
+use core::f32::consts::PI;
+
+use bevy::prelude::*;
+
+/// 2D poly6 kernel, used for density estimation.
+pub fn poly6(distance_squared: f32, smoothing_radius: f32) -> f32 {
+    let h_squared = smoothing_radius * smoothing_radius;
+    if distance_squared >= h_squared {
+        return 0.;
+    }
+    4. / (PI * smoothing_radius.powi(8)) * (h_squared - distance_squared).powi(3)
+}
+
+/// Gradient of the 2D spiky kernel, used for pressure forces.
+/// `offset` points from the neighbour to the particle.
+pub fn spiky_gradient(offset: Vec2, smoothing_radius: f32) -> Vec2 {
+    let distance = offset.length();
+    if distance >= smoothing_radius || distance == 0. {
+        return Vec2::ZERO;
+    }
+    -30. / (PI * smoothing_radius.powi(5)) * (smoothing_radius - distance).powi(2) * offset
+        / distance
+}
+
+/// Laplacian of the 2D viscosity kernel, used for viscosity forces.
+pub fn viscosity_laplacian(distance: f32, smoothing_radius: f32) -> f32 {
+    if distance >= smoothing_radius {
+        return 0.;
+    }
+    40. / (PI * smoothing_radius.powi(5)) * (smoothing_radius - distance)
+}
@@ -0,0 +1,61 @@
+#[cfg(test)]
+mod kernels_tests {
+    use super::super::*;
+
+    const SMOOTHING_RADIUS: f32 = 0.3;
+
+    /// Sum of `kernel` over a fine grid covering its support, times the area
+    /// of a grid cell.
+    fn integrate(kernel: impl Fn(Vec2) -> f32) -> f32 {
+        let steps = 400;
+        let cell = 2. * SMOOTHING_RADIUS / steps as f32;
+        (0..steps)
+            .flat_map(|x| (0..steps).map(move |y| (x, y)))
+            .map(|(x, y)| {
+                let point = (Vec2::new(x as f32, y as f32) + 0.5) * cell - SMOOTHING_RADIUS;
+                kernel(point)
+            })
+            .sum::<f32>()
+            * cell
+            * cell
+    }
+
+    #[test]
+    fn poly6_is_normalised() {
+        let integral = integrate(|point| poly6(point.length_squared(), SMOOTHING_RADIUS));
+        assert!((integral - 1.).abs() < 1e-3, "{integral}");
+    }
+
+    #[test]
+    fn spiky_gradient_is_the_slope_of_the_normalised_spiky_kernel() {
+        let spiky = |distance: f32| {
+            10. / (PI * SMOOTHING_RADIUS.powi(5)) * (SMOOTHING_RADIUS - distance).max(0.).powi(3)
+        };
+        let integral = integrate(|point| spiky(point.length()));
+        assert!((integral - 1.).abs() < 1e-3, "{integral}");
+
+        let epsilon = 1e-4;
+        for distance in [0.05, 0.1, 0.2, 0.29] {
+            let slope = (spiky(distance + epsilon) - spiky(distance - epsilon)) / (2. * epsilon);
+            let gradient = spiky_gradient(Vec2::new(0., distance), SMOOTHING_RADIUS);
+            assert_eq!(gradient.x, 0.);
+            assert!(
+                (gradient.y - slope).abs() < 1e-2 * slope.abs(),
+                "{gradient} {slope}"
+            );
+        }
+    }
+
+    #[test]
+    fn kernels_are_zero_from_the_smoothing_radius_on() {
+        for distance in [SMOOTHING_RADIUS, SMOOTHING_RADIUS * 1.5] {
+            assert_eq!(poly6(distance * distance, SMOOTHING_RADIUS), 0.);
+            assert_eq!(
+                spiky_gradient(Vec2::new(distance, 0.), SMOOTHING_RADIUS),
+                Vec2::ZERO
+            );
+            assert_eq!(viscosity_laplacian(distance, SMOOTHING_RADIUS), 0.);
+        }
+        assert!(viscosity_laplacian(SMOOTHING_RADIUS * 0.5, SMOOTHING_RADIUS) > 0.);
+    }
+}
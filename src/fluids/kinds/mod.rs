@@ -0,0 +1,245 @@
+mod tests;
+
+use bevy::prelude::*;
+
+use super::particle::FluidParticle;
+use crate::kinetics::mass::Mass;
+
+/// Which `FluidMaterial` of the `FluidKinds` a particle is made of.
+#[derive(Component, Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub struct FluidKind(pub usize);
+
+/// One phase of the fluid. Densities and viscosities are in the units of
+/// `SphSettings`, and only drive the SPH pipeline of `Solver::Forces`; the
+/// other solvers still use the mass, restitution and colour.
+#[derive(Clone, Debug)]
+pub struct FluidMaterial {
+    pub rest_density: f32,
+    pub viscosity: f32,
+    /// Cohesion between two particles of this kind, which pulls a free
+    /// surface together.
+    pub surface_tension: f32,
+    /// `None` gives every particle a random hue.
+    pub color: Option<Color>,
+    pub restitution_coeff: f32,
+    pub friction_coeff: f32,
+    pub particle_mass: f32,
+}
+
+impl FluidMaterial {
+    pub fn particle(&self, radius: f32) -> (FluidParticle, Mass) {
+        (
+            FluidParticle {
+                radius,
+                restitution_coeff: self.restitution_coeff,
+                friction_coeff: self.friction_coeff,
+            },
+            Mass(self.particle_mass),
+        )
+    }
+}
+
+/// How the particles of two different kinds act on each other.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct KindInteraction {
+    pub viscosity: f32,
+    /// Like `FluidMaterial::surface_tension`. Negative values push the kinds
+    /// apart, which keeps immiscible phases from mixing.
+    pub cohesion: f32,
+}
+
+/// Fluids the particles are made of, chosen at startup and cycled with the
+/// controls.
+#[derive(Resource, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Phases {
+    /// A single kind of water, which is what every particle was before there
+    /// were several kinds.
+    #[default]
+    Water,
+    /// Oil floating on water: two immiscible phases, the lighter on top.
+    OilAndWater,
+    /// A heavy phase resting on a light one it doesn't mix with, whose
+    /// interface rolls up into the fingers of a Rayleigh–Taylor instability.
+    RayleighTaylor,
+}
+
+/// Registry of the fluid kinds, indexed by `FluidKind`, with the rules
+/// between every pair of them. Registration order is also the order of the
+/// layers the particles are spawned in, from the bottom up.
+#[derive(Resource, Clone, Debug)]
+pub struct FluidKinds {
+    materials: Vec<FluidMaterial>,
+    /// Symmetric, `materials.len()` squared; the diagonal is unused.
+    interactions: Vec<KindInteraction>,
+}
+
+impl FluidKinds {
+    pub fn new(phases: Phases) -> FluidKinds {
+        match phases {
+            Phases::Water => {
+                let mut kinds = FluidKinds::empty();
+                kinds.register(water());
+                kinds
+            }
+            Phases::OilAndWater => FluidKinds::oil_and_water(),
+            Phases::RayleighTaylor => FluidKinds::rayleigh_taylor(),
+        }
+    }
+
+    pub fn empty() -> FluidKinds {
+        FluidKinds {
+            materials: Vec::new(),
+            interactions: Vec::new(),
+        }
+    }
+
+    /// Adds `material`, which mixes with the kinds already registered: their
+    /// viscosity is the mean of both and they don't stick to each other more
+    /// than to themselves.
+    pub fn register(&mut self, material: FluidMaterial) -> FluidKind {
+        let amount = self.materials.len() + 1;
+        let mut interactions = Vec::with_capacity(amount * amount);
+        for a in 0..amount {
+            for b in 0..amount {
+                interactions.push(if a < amount - 1 && b < amount - 1 {
+                    self.interactions[a * (amount - 1) + b]
+                } else {
+                    let other = &self.materials.get(a.min(b)).unwrap_or(&material);
+                    KindInteraction {
+                        viscosity: 0.5 * (other.viscosity + material.viscosity),
+                        cohesion: other.surface_tension.min(material.surface_tension),
+                    }
+                });
+            }
+        }
+        self.materials.push(material);
+        self.interactions = interactions;
+        FluidKind(amount - 1)
+    }
+
+    pub fn set_interaction(&mut self, a: FluidKind, b: FluidKind, interaction: KindInteraction) {
+        let amount = self.materials.len();
+        self.interactions[a.0 * amount + b.0] = interaction;
+        self.interactions[b.0 * amount + a.0] = interaction;
+    }
+
+    /// `None` for kinds that aren't registered, like those of particles
+    /// left over from other phases.
+    pub fn material(&self, kind: FluidKind) -> Option<&FluidMaterial> {
+        self.materials.get(kind.0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (FluidKind, &FluidMaterial)> {
+        self.materials
+            .iter()
+            .enumerate()
+            .map(|(idx, material)| (FluidKind(idx), material))
+    }
+
+    /// How particles of kinds `a` and `b` act on each other, `None` if
+    /// either kind isn't registered.
+    fn interaction(&self, a: FluidKind, b: FluidKind) -> Option<KindInteraction> {
+        let amount = self.materials.len();
+        if a.0 >= amount || b.0 >= amount {
+            return None;
+        }
+        Some(if a == b {
+            let material = &self.materials[a.0];
+            KindInteraction {
+                viscosity: material.viscosity,
+                cohesion: material.surface_tension,
+            }
+        } else {
+            self.interactions[a.0 * amount + b.0]
+        })
+    }
+
+    /// Viscosity between particles of kinds `a` and `b`, zero if either
+    /// isn't registered.
+    pub fn viscosity(&self, a: FluidKind, b: FluidKind) -> f32 {
+        self.interaction(a, b)
+            .map_or(0., |interaction| interaction.viscosity)
+    }
+
+    /// Cohesion between particles of kinds `a` and `b`, zero if either isn't
+    /// registered.
+    pub fn cohesion(&self, a: FluidKind, b: FluidKind) -> f32 {
+        self.interaction(a, b)
+            .map_or(0., |interaction| interaction.cohesion)
+    }
+
+    /// Whether any pair of kinds attracts or repels, so that cohesion forces
+    /// have to be computed at all.
+    pub fn has_cohesion(&self) -> bool {
+        let amount = self.materials.len();
+        (0..amount).any(|a| (0..amount).any(|b| self.cohesion(FluidKind(a), FluidKind(b)) != 0.))
+    }
+
+    fn oil_and_water() -> FluidKinds {
+        let mut kinds = FluidKinds::empty();
+        let water = kinds.register(FluidMaterial {
+            surface_tension: 0.4,
+            color: Some(Color::hsl(210., 0.9, 0.6)),
+            ..water()
+        });
+        let oil = kinds.register(FluidMaterial {
+            rest_density: 24.,
+            viscosity: 9.,
+            surface_tension: 0.2,
+            color: Some(Color::hsl(45., 0.95, 0.55)),
+            restitution_coeff: 0.9,
+            friction_coeff: 0.2,
+            particle_mass: 0.8,
+        });
+        kinds.set_interaction(
+            water,
+            oil,
+            KindInteraction {
+                viscosity: 4.5,
+                cohesion: -0.4,
+            },
+        );
+        kinds
+    }
+
+    fn rayleigh_taylor() -> FluidKinds {
+        let mut kinds = FluidKinds::empty();
+        let light = kinds.register(FluidMaterial {
+            surface_tension: 0.1,
+            color: Some(Color::hsl(190., 0.8, 0.65)),
+            ..water()
+        });
+        let heavy = kinds.register(FluidMaterial {
+            rest_density: 60.,
+            surface_tension: 0.1,
+            color: Some(Color::hsl(340., 0.85, 0.55)),
+            particle_mass: 2.,
+            ..water()
+        });
+        kinds.set_interaction(
+            light,
+            heavy,
+            KindInteraction {
+                viscosity: 1.5,
+                cohesion: -0.1,
+            },
+        );
+        kinds
+    }
+}
+
+pub fn select_fluid_kinds(phases: Res<Phases>, mut fluid_kinds: ResMut<FluidKinds>) {
+    *fluid_kinds = FluidKinds::new(*phases);
+}
+
+fn water() -> FluidMaterial {
+    FluidMaterial {
+        rest_density: 30.,
+        viscosity: 1.5,
+        surface_tension: 0.,
+        color: None,
+        restitution_coeff: 0.97,
+        friction_coeff: 0.1,
+        particle_mass: 1.,
+    }
+}
@@ -0,0 +1,72 @@
+#[cfg(test)]
+mod fluid_kinds_tests {
+    use super::super::*;
+
+    #[test]
+    fn registered_kinds_mix_with_the_previous_ones() {
+        let mut kinds = FluidKinds::empty();
+        let thin = kinds.register(FluidMaterial {
+            viscosity: 0.1,
+            surface_tension: 0.2,
+            ..water()
+        });
+        let thick = kinds.register(FluidMaterial {
+            viscosity: 0.3,
+            surface_tension: 0.4,
+            ..water()
+        });
+        assert_eq!(kinds.viscosity(thin, thin), 0.1);
+        assert_eq!(kinds.viscosity(thick, thick), 0.3);
+        assert!((kinds.viscosity(thin, thick) - 0.2).abs() < 1e-6);
+        assert_eq!(kinds.cohesion(thick, thin), 0.2);
+        assert_eq!(kinds.cohesion(thick, thick), 0.4);
+
+        let third = kinds.register(water());
+        assert!((kinds.viscosity(thin, thick) - 0.2).abs() < 1e-6);
+        assert_eq!(kinds.cohesion(third, thin), 0.);
+    }
+
+    #[test]
+    fn interactions_are_the_same_both_ways() {
+        let mut kinds = FluidKinds::new(Phases::Water);
+        let oil = kinds.register(water());
+        let interaction = KindInteraction {
+            viscosity: 0.7,
+            cohesion: -1.,
+        };
+        kinds.set_interaction(oil, FluidKind(0), interaction);
+        assert_eq!(kinds.viscosity(FluidKind(0), oil), 0.7);
+        assert_eq!(kinds.cohesion(FluidKind(0), oil), -1.);
+        assert_eq!(kinds.cohesion(oil, FluidKind(0)), -1.);
+        assert_eq!(kinds.cohesion(oil, oil), 0.);
+    }
+
+    #[test]
+    fn phases_stack_their_kinds_by_density() {
+        let density_of =
+            |phases, kind| FluidKinds::new(phases).material(kind).unwrap().rest_density;
+        // Stable layering, lighter on top.
+        assert!(
+            density_of(Phases::OilAndWater, FluidKind(1))
+                < density_of(Phases::OilAndWater, FluidKind(0))
+        );
+        // Unstable layering, heavier on top.
+        assert!(
+            density_of(Phases::RayleighTaylor, FluidKind(1))
+                > density_of(Phases::RayleighTaylor, FluidKind(0))
+        );
+        for phases in [Phases::OilAndWater, Phases::RayleighTaylor] {
+            assert!(FluidKinds::new(phases).cohesion(FluidKind(0), FluidKind(1)) < 0.);
+        }
+        assert!(!FluidKinds::new(Phases::Water).has_cohesion());
+    }
+
+    #[test]
+    fn kinds_of_other_phases_are_inert() {
+        let kinds = FluidKinds::new(Phases::Water);
+        let stale = FluidKind(1);
+        assert!(kinds.material(stale).is_none());
+        assert_eq!(kinds.viscosity(FluidKind(0), stale), 0.);
+        assert_eq!(kinds.cohesion(stale, stale), 0.);
+    }
+}
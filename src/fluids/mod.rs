@@ -1 +1,38 @@
-pub mod particle;
\ No newline at end of file
+pub mod density;
+pub mod flip;
+pub mod kernels;
+pub mod kinds;
+pub mod particle;
+pub mod position_based;
+pub mod pressure;
+pub mod stable_fluids;
+pub mod surface_tension;
+pub mod viscosity;
+
+use bevy::prelude::*;
+
+use crate::kinetics::velocity::PIXELS_PER_METER;
+
+/// Parameters of the smoothed-particle hydrodynamics pipeline.
+/// Lengths are in pixels, everything else is in SI units. The rest densities
+/// and viscosities are those of the `FluidKinds`.
+#[derive(Resource, Clone, Copy)]
+pub struct SphSettings {
+    pub smoothing_radius: f32,
+    pub stiffness: f32,
+}
+
+impl SphSettings {
+    pub fn smoothing_radius_in_meters(&self) -> f32 {
+        self.smoothing_radius / PIXELS_PER_METER
+    }
+}
+
+impl Default for SphSettings {
+    fn default() -> Self {
+        SphSettings {
+            smoothing_radius: 12.,
+            stiffness: 8.,
+        }
+    }
+}
@@ -3,11 +3,14 @@ use bevy::prelude::*;
 #[derive(Component,Clone, Copy)]
 pub struct FluidParticle {
     pub radius: f32,
-    pub restitution_coeff: f32
+    pub restitution_coeff: f32,
+    /// Coulomb friction coefficient, combined with the other particle's as
+    /// their geometric mean.
+    pub friction_coeff: f32,
 }
 
-impl Into<Mesh> for FluidParticle {
-    fn into(self) -> Mesh {
-        Circle::new(self.radius).into()
+impl From<FluidParticle> for Mesh {
+    fn from(val: FluidParticle) -> Self {
+        Circle::new(val.radius).into()
     }
 }
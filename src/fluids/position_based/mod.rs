@@ -0,0 +1,224 @@
+mod tests;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use bevy::{prelude::*, utils::HashMap};
+
+use super::{kernels, particle::FluidParticle};
+use crate::{
+    kinetics::{
+        bounds::{periodic::Periodicity, Boundary},
+        collisions::position_hashing::PositionHashMap,
+        forces::Forces,
+        limiter::MotionLimits,
+        mass::Mass,
+        velocity::{Velocity, PIXELS_PER_METER},
+    },
+    performance_monitor::LimiterMonitor,
+};
+
+/// Parameters of the Position Based Fluids solver (Macklin & Müller, 2013).
+/// Lengths are in pixels, everything else is in SI units.
+#[derive(Resource, Clone, Copy)]
+pub struct PbfSettings {
+    pub smoothing_radius: f32,
+    pub rest_density: f32,
+    pub solver_iterations: usize,
+    /// Constraint force mixing term that keeps the lambdas bounded when a
+    /// particle has almost no neighbours.
+    pub relaxation: f32,
+    /// Strength, reference distance (as a fraction of the smoothing radius)
+    /// and exponent of the artificial pressure that prevents clustering.
+    pub tensile_instability_strength: f32,
+    pub tensile_instability_distance: f32,
+    pub tensile_instability_exponent: i32,
+}
+
+impl Default for PbfSettings {
+    fn default() -> Self {
+        PbfSettings {
+            smoothing_radius: 12.,
+            rest_density: 50.,
+            solver_iterations: 4,
+            relaxation: 5.,
+            tensile_instability_strength: 0.001,
+            tensile_instability_distance: 0.2,
+            tensile_instability_exponent: 4,
+        }
+    }
+}
+
+#[derive(Component, Clone, Copy, Default)]
+pub struct PredictedPosition(pub Vec2);
+
+pub fn predict_positions(
+    time: Res<Time>,
+    mut particles_q: Query<(
+        &Transform,
+        &Mass,
+        &mut Velocity,
+        &Forces,
+        &mut PredictedPosition,
+    )>,
+) {
+    let delta = time.delta().as_secs_f32();
+    particles_q.par_iter_mut().for_each(
+        |(transform, Mass(mass), mut velocity, forces, mut predicted_position)| {
+            velocity.0 += forces.total() / mass * delta;
+            predicted_position.0 =
+                transform.translation.xy() + velocity.0 * delta * PIXELS_PER_METER;
+        },
+    );
+}
+
+pub fn solve_density_constraints(
+    pbf_settings: Res<PbfSettings>,
+    position_hash_map: Res<PositionHashMap>,
+    periodicity: Res<Periodicity>,
+    boundaries_q: Query<(&Boundary, &Transform), Without<FluidParticle>>,
+    mut particles_q: Query<(Entity, &FluidParticle, &Mass, &mut PredictedPosition)>,
+) {
+    let entities: Vec<Entity> = particles_q.iter().map(|(entity, ..)| entity).collect();
+    let indices: HashMap<Entity, usize> = entities
+        .iter()
+        .enumerate()
+        .map(|(idx, entity)| (*entity, idx))
+        .collect();
+    let radii: Vec<f32> = particles_q
+        .iter()
+        .map(|(_, particle, ..)| particle.radius)
+        .collect();
+    let masses: Vec<f32> = particles_q
+        .iter()
+        .map(|(_, _, Mass(mass), _)| *mass)
+        .collect();
+    let mut positions: Vec<Vec2> = particles_q
+        .iter()
+        .map(|(.., predicted_position)| predicted_position.0)
+        .collect();
+
+    // The hash map still holds the positions from before the prediction, so
+    // the search radius is widened to catch particles that moved into range.
+    let neighbours: Vec<Vec<usize>> = positions
+        .iter()
+        .map(|position| {
+            position_hash_map
+                .entities_near(*position, 1.5 * pbf_settings.smoothing_radius)
+                .into_iter()
+                .filter_map(|neighbour| indices.get(&neighbour).copied())
+                .collect()
+        })
+        .collect();
+
+    let smoothing_radius = pbf_settings.smoothing_radius / PIXELS_PER_METER;
+    let tensile_instability_reference = kernels::poly6(
+        (pbf_settings.tensile_instability_distance * smoothing_radius).powi(2),
+        smoothing_radius,
+    );
+    let mut lambdas = vec![0.; positions.len()];
+
+    for _ in 0..pbf_settings.solver_iterations {
+        for (idx, lambda) in lambdas.iter_mut().enumerate() {
+            let mut density = 0.;
+            let mut own_gradient = Vec2::ZERO;
+            let mut sum_of_squared_gradients = 0.;
+            for &neighbour in &neighbours[idx] {
+                let offset =
+                    periodicity.offset(positions[neighbour], positions[idx]) / PIXELS_PER_METER;
+                density +=
+                    masses[neighbour] * kernels::poly6(offset.length_squared(), smoothing_radius);
+                if neighbour != idx {
+                    let gradient = masses[neighbour] / pbf_settings.rest_density
+                        * kernels::spiky_gradient(offset, smoothing_radius);
+                    own_gradient += gradient;
+                    sum_of_squared_gradients += gradient.length_squared();
+                }
+            }
+            // Only compression is corrected, a free surface would otherwise be
+            // pulled together into clumps.
+            let constraint = (density / pbf_settings.rest_density - 1.).max(0.);
+            *lambda = -constraint
+                / (sum_of_squared_gradients
+                    + own_gradient.length_squared()
+                    + pbf_settings.relaxation);
+        }
+
+        let corrections: Vec<Vec2> = (0..positions.len())
+            .map(|idx| {
+                neighbours[idx]
+                    .iter()
+                    .filter(|neighbour| **neighbour != idx)
+                    .map(|&neighbour| {
+                        let offset = periodicity.offset(positions[neighbour], positions[idx])
+                            / PIXELS_PER_METER;
+                        let tensile_instability = -pbf_settings.tensile_instability_strength
+                            * (kernels::poly6(offset.length_squared(), smoothing_radius)
+                                / tensile_instability_reference)
+                                .powi(pbf_settings.tensile_instability_exponent);
+                        (lambdas[idx] + lambdas[neighbour] + tensile_instability)
+                            * masses[neighbour]
+                            / pbf_settings.rest_density
+                            * kernels::spiky_gradient(offset, smoothing_radius)
+                    })
+                    .sum::<Vec2>()
+                    * PIXELS_PER_METER
+            })
+            .collect();
+
+        for (idx, position) in positions.iter_mut().enumerate() {
+            *position = periodicity.wrap(*position + corrections[idx]);
+            // Projected out of the walls, like `enforce_bounds` does with the
+            // particles themselves.
+            for (boundary, boundary_transform) in boundaries_q.iter() {
+                if let Some((penetration, normal)) =
+                    boundary.penetration(boundary_transform, *position, radii[idx], &periodicity)
+                {
+                    *position -= penetration * normal;
+                }
+            }
+        }
+    }
+
+    for (entity, .., mut predicted_position) in particles_q.iter_mut() {
+        predicted_position.0 = positions[indices[&entity]];
+    }
+}
+
+/// Derives the velocities from the corrected displacements. Those are
+/// shortened to `MotionLimits::max_velocity` here rather than before the
+/// prediction, as the constraints can move the particles further.
+pub fn update_velocities_and_positions(
+    time: Res<Time>,
+    periodicity: Res<Periodicity>,
+    motion_limits: Res<MotionLimits>,
+    limiter_monitor: Option<ResMut<LimiterMonitor>>,
+    mut particles_q: Query<(&PredictedPosition, &mut Transform, &mut Velocity)>,
+) {
+    let delta = time.delta().as_secs_f32();
+    if delta == 0. {
+        return;
+    }
+    let clamped = AtomicUsize::new(0);
+    particles_q
+        .par_iter_mut()
+        .for_each(|(predicted_position, mut transform, mut velocity)| {
+            let position = transform.translation.xy();
+            let (limited, is_clamped) = motion_limits.limit_velocity(
+                periodicity.offset(position, predicted_position.0) / (delta * PIXELS_PER_METER),
+            );
+            let predicted_position = if is_clamped {
+                clamped.fetch_add(1, Ordering::Relaxed);
+                position + limited * delta * PIXELS_PER_METER
+            } else {
+                predicted_position.0
+            };
+            velocity.0 = limited;
+            transform.translation = periodicity
+                .wrap(predicted_position)
+                .extend(transform.translation.z);
+        });
+    if let Some(mut limiter_monitor) = limiter_monitor {
+        limiter_monitor.clamped_velocities =
+            limiter_monitor.clamped_velocities.max(clamped.into_inner());
+    }
+}
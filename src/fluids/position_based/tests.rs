@@ -0,0 +1,70 @@
+#[cfg(test)]
+mod position_based_tests {
+    use std::time::Duration;
+
+    use super::super::*;
+    use crate::kinetics::collisions::position_hashing::{
+        update_position_map, PositionHashingPlugin,
+    };
+
+    #[test]
+    fn velocities_stay_under_the_limit_after_the_constraints() {
+        let mut app = App::new();
+        app.add_plugins(PositionHashingPlugin)
+            .insert_resource(Periodicity::default())
+            .insert_resource(PbfSettings::default())
+            .insert_resource(MotionLimits {
+                enabled: true,
+                max_acceleration: None,
+                max_velocity: Some(1.),
+            })
+            .insert_resource(LimiterMonitor::default());
+        let mut time = Time::<()>::default();
+        time.advance_by(Duration::from_secs_f32(1. / 144.));
+        app.insert_resource(time);
+        app.world_mut().run_schedule(Startup);
+
+        let world = app.world_mut();
+        let particles: Vec<Entity> = (0..9)
+            .map(|i| {
+                world
+                    .spawn((
+                        FluidParticle {
+                            radius: 3.,
+                            restitution_coeff: 1.,
+                            friction_coeff: 0.,
+                        },
+                        Transform::from_xyz((i % 3) as f32, (i / 3) as f32, 0.),
+                        Mass(1.),
+                        Velocity(Vec2::new(20., 0.)),
+                        Forces::default(),
+                        PredictedPosition::default(),
+                    ))
+                    .id()
+            })
+            .collect();
+        world.run_system_cached(update_position_map).unwrap();
+        let before: Vec<Vec2> = particles
+            .iter()
+            .map(|&particle| world.get::<Transform>(particle).unwrap().translation.xy())
+            .collect();
+
+        world.run_system_cached(predict_positions).unwrap();
+        world.run_system_cached(solve_density_constraints).unwrap();
+        world
+            .run_system_cached(update_velocities_and_positions)
+            .unwrap();
+
+        let delta = 1. / 144.;
+        for (&particle, before) in particles.iter().zip(before) {
+            let velocity = world.get::<Velocity>(particle).unwrap().0;
+            let position = world.get::<Transform>(particle).unwrap().translation.xy();
+            assert!(velocity.length() <= 1. + 1e-4, "{velocity}");
+            assert!(
+                (position - before).length() <= delta * PIXELS_PER_METER + 1e-3,
+                "{position} {before}"
+            );
+        }
+        assert!(world.resource::<LimiterMonitor>().clamped_velocities > 0);
+    }
+}
@@ -0,0 +1,91 @@
+use bevy::prelude::*;
+
+use super::{
+    density::Density,
+    kernels,
+    kinds::{FluidKind, FluidKinds},
+    particle::FluidParticle,
+    SphSettings,
+};
+use crate::kinetics::{
+    bounds::periodic::Periodicity,
+    collisions::position_hashing::PositionHashMap,
+    forces::{ForceSource, Forces},
+    mass::Mass,
+    velocity::PIXELS_PER_METER,
+};
+
+#[derive(Component, Clone, Copy, Default)]
+pub struct Pressure(pub f32);
+
+pub fn calculate_pressures(
+    sph_settings: Res<SphSettings>,
+    fluid_kinds: Res<FluidKinds>,
+    mut particles_q: Query<(&FluidKind, &Density, &mut Pressure), With<FluidParticle>>,
+) {
+    particles_q
+        .par_iter_mut()
+        .for_each(|(kind, Density(density), mut pressure)| {
+            let Some(material) = fluid_kinds.material(*kind) else {
+                pressure.0 = 0.;
+                return;
+            };
+            let rest_density = material.rest_density;
+            // Negative pressures make the particles clump together, so a
+            // rarefied region simply exerts no pressure.
+            pressure.0 = (sph_settings.stiffness * (density - rest_density)).max(0.);
+        });
+}
+
+/// Pressure forces of the multiphase formulation of Solenthaler and Pajarola,
+/// weighted by number densities rather than mass densities. With a single
+/// mass it is the usual symmetric SPH pressure force.
+pub fn apply_pressure_forces(
+    sph_settings: Res<SphSettings>,
+    position_hash_map: Res<PositionHashMap>,
+    periodicity: Res<Periodicity>,
+    mut particles_q: Query<(Entity, &Transform, &Mass, &Density, &Pressure, &mut Forces)>,
+    neighbours_q: Query<(&Transform, &Mass, &Density, &Pressure), With<FluidParticle>>,
+) {
+    let smoothing_radius = sph_settings.smoothing_radius_in_meters();
+    particles_q.par_iter_mut().for_each(
+        |(entity, transform, Mass(mass), density, Pressure(pressure), mut forces)| {
+            if density.0 <= 0. {
+                return;
+            }
+            let center = transform.translation.xy();
+            let number_density = density.number_density(*mass);
+            let own_term = pressure / (number_density * number_density);
+
+            let pressure_force: Vec2 = position_hash_map
+                .entities_near(center, sph_settings.smoothing_radius)
+                .into_iter()
+                .filter(|neighbour| *neighbour != entity)
+                .filter_map(|neighbour| neighbours_q.get(neighbour).ok())
+                .filter(|(_, _, Density(neighbour_density), _)| *neighbour_density > 0.)
+                .map(
+                    |(
+                        neighbour_transform,
+                        Mass(neighbour_mass),
+                        neighbour_density,
+                        Pressure(neighbour_pressure),
+                    )| {
+                        let offset = periodicity
+                            .offset(neighbour_transform.translation.xy(), center)
+                            / PIXELS_PER_METER;
+                        let neighbour_number_density =
+                            neighbour_density.number_density(*neighbour_mass);
+                        -(own_term
+                            + neighbour_pressure
+                                / (neighbour_number_density * neighbour_number_density))
+                            * kernels::spiky_gradient(offset, smoothing_radius)
+                    },
+                )
+                .sum();
+
+            if pressure_force != Vec2::ZERO {
+                forces.add(ForceSource::Pressure, pressure_force);
+            }
+        },
+    );
+}
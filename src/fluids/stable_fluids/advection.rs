@@ -0,0 +1,49 @@
+use bevy::prelude::*;
+
+use super::{mac_grid::MacGrid, StableFluidsSettings};
+
+/// Semi-Lagrangian advection of the velocity field by itself.
+pub fn advect_velocity(time: Res<Time>, mut grid: ResMut<MacGrid>) {
+    let delta = time.delta().as_secs_f32();
+    let mut new_u = grid.u.clone();
+    let mut new_v = grid.v.clone();
+
+    for j in 0..grid.height {
+        for i in 1..grid.width {
+            let position = grid.u_position(i, j);
+            let departure = position - grid.velocity_at(position) * delta;
+            new_u[grid.u_idx(i, j)] = grid.sample_u(&grid.u, departure);
+        }
+    }
+    for j in 1..grid.height {
+        for i in 0..grid.width {
+            let position = grid.v_position(i, j);
+            let departure = position - grid.velocity_at(position) * delta;
+            new_v[grid.v_idx(i, j)] = grid.sample_v(&grid.v, departure);
+        }
+    }
+
+    grid.u = new_u;
+    grid.v = new_v;
+}
+
+/// Semi-Lagrangian advection of the dye by the velocity field.
+pub fn advect_dye(
+    time: Res<Time>,
+    stable_fluids_settings: Res<StableFluidsSettings>,
+    mut grid: ResMut<MacGrid>,
+) {
+    let delta = time.delta().as_secs_f32();
+    let retained = 1. - stable_fluids_settings.dye_dissipation * delta;
+    let mut new_dye = grid.dye.clone();
+
+    for j in 0..grid.height {
+        for i in 0..grid.width {
+            let position = grid.cell_center(i, j);
+            let departure = position - grid.velocity_at(position) * delta;
+            new_dye[grid.cell_idx(i, j)] = grid.sample_center(&grid.dye, departure) * retained;
+        }
+    }
+
+    grid.dye = new_dye;
+}
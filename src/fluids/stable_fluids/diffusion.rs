@@ -0,0 +1,93 @@
+use bevy::prelude::*;
+
+use super::{mac_grid::MacGrid, StableFluidsSettings};
+
+/// Implicit viscous diffusion of both velocity components.
+pub fn diffuse_velocity(
+    time: Res<Time>,
+    stable_fluids_settings: Res<StableFluidsSettings>,
+    mut grid: ResMut<MacGrid>,
+) {
+    let rate = stable_fluids_settings.viscosity * time.delta().as_secs_f32()
+        / (grid.cell_size * grid.cell_size);
+    if rate == 0. {
+        return;
+    }
+    let (width, height) = (grid.width, grid.height);
+    let iterations = stable_fluids_settings.diffusion_iterations;
+
+    // The faces on the walls are left untouched so the no-through-flow
+    // condition survives the diffusion.
+    let u = std::mem::take(&mut grid.u);
+    grid.u = diffuse(&u, width + 1, height, rate, iterations, |i, _| {
+        i == 0 || i == width
+    });
+    let v = std::mem::take(&mut grid.v);
+    grid.v = diffuse(&v, width, height + 1, rate, iterations, |_, j| {
+        j == 0 || j == height
+    });
+}
+
+pub fn diffuse_dye(
+    time: Res<Time>,
+    stable_fluids_settings: Res<StableFluidsSettings>,
+    mut grid: ResMut<MacGrid>,
+) {
+    let rate = stable_fluids_settings.dye_diffusion * time.delta().as_secs_f32()
+        / (grid.cell_size * grid.cell_size);
+    if rate == 0. {
+        return;
+    }
+    let (width, height) = (grid.width, grid.height);
+    let dye = std::mem::take(&mut grid.dye);
+    grid.dye = diffuse(
+        &dye,
+        width,
+        height,
+        rate,
+        stable_fluids_settings.diffusion_iterations,
+        |_, _| false,
+    );
+}
+
+/// Solves `(I - rate * laplacian) x = initial` with Gauss-Seidel iterations.
+/// Samples outside of the grid mirror their neighbour, so nothing diffuses
+/// through the walls.
+fn diffuse(
+    initial: &[f32],
+    columns: usize,
+    rows: usize,
+    rate: f32,
+    iterations: usize,
+    is_fixed: impl Fn(usize, usize) -> bool,
+) -> Vec<f32> {
+    let mut result = initial.to_vec();
+    for _ in 0..iterations {
+        for j in 0..rows {
+            for i in 0..columns {
+                if is_fixed(i, j) {
+                    continue;
+                }
+                let idx = i + j * columns;
+                let left = if i > 0 { result[idx - 1] } else { result[idx] };
+                let right = if i + 1 < columns {
+                    result[idx + 1]
+                } else {
+                    result[idx]
+                };
+                let down = if j > 0 {
+                    result[idx - columns]
+                } else {
+                    result[idx]
+                };
+                let up = if j + 1 < rows {
+                    result[idx + columns]
+                } else {
+                    result[idx]
+                };
+                result[idx] = (initial[idx] + rate * (left + right + down + up)) / (1. + 4. * rate);
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,107 @@
+use bevy::prelude::*;
+
+/// Staggered (marker-and-cell) grid. Horizontal velocities live on the
+/// vertical cell faces, vertical velocities on the horizontal cell faces and
+/// scalar quantities at the cell centers.
+/// Positions are in meters relative to the lower left corner of the grid.
+#[derive(Resource, Clone)]
+pub struct MacGrid {
+    pub width: usize,
+    pub height: usize,
+    pub cell_size: f32,
+    /// `(width + 1) * height` horizontal velocities.
+    pub u: Vec<f32>,
+    /// `width * (height + 1)` vertical velocities.
+    pub v: Vec<f32>,
+    /// `width * height` dye concentrations.
+    pub dye: Vec<f32>,
+}
+
+impl MacGrid {
+    pub fn new(width: usize, height: usize, cell_size: f32) -> MacGrid {
+        MacGrid {
+            width,
+            height,
+            cell_size,
+            u: vec![0.; (width + 1) * height],
+            v: vec![0.; width * (height + 1)],
+            dye: vec![0.; width * height],
+        }
+    }
+
+    pub fn u_idx(&self, i: usize, j: usize) -> usize {
+        i + j * (self.width + 1)
+    }
+
+    pub fn v_idx(&self, i: usize, j: usize) -> usize {
+        i + j * self.width
+    }
+
+    pub fn cell_idx(&self, i: usize, j: usize) -> usize {
+        i + j * self.width
+    }
+
+    pub fn u_position(&self, i: usize, j: usize) -> Vec2 {
+        Vec2::new(i as f32, j as f32 + 0.5) * self.cell_size
+    }
+
+    pub fn v_position(&self, i: usize, j: usize) -> Vec2 {
+        Vec2::new(i as f32 + 0.5, j as f32) * self.cell_size
+    }
+
+    pub fn cell_center(&self, i: usize, j: usize) -> Vec2 {
+        Vec2::new(i as f32 + 0.5, j as f32 + 0.5) * self.cell_size
+    }
+
+    pub fn sample_u(&self, u: &[f32], position: Vec2) -> f32 {
+        sample(
+            u,
+            self.width + 1,
+            self.height,
+            position / self.cell_size - Vec2::new(0., 0.5),
+        )
+    }
+
+    pub fn sample_v(&self, v: &[f32], position: Vec2) -> f32 {
+        sample(
+            v,
+            self.width,
+            self.height + 1,
+            position / self.cell_size - Vec2::new(0.5, 0.),
+        )
+    }
+
+    pub fn sample_center(&self, field: &[f32], position: Vec2) -> f32 {
+        sample(
+            field,
+            self.width,
+            self.height,
+            position / self.cell_size - Vec2::splat(0.5),
+        )
+    }
+
+    pub fn velocity_at(&self, position: Vec2) -> Vec2 {
+        Vec2::new(
+            self.sample_u(&self.u, position),
+            self.sample_v(&self.v, position),
+        )
+    }
+}
+
+/// Bilinearly interpolates `field`, a `columns * rows` row-major array, at
+/// `grid_position` given in sample units. Positions outside of the array are
+/// clamped onto its edge.
+fn sample(field: &[f32], columns: usize, rows: usize, grid_position: Vec2) -> f32 {
+    let x = grid_position.x.clamp(0., (columns - 1) as f32);
+    let y = grid_position.y.clamp(0., (rows - 1) as f32);
+    let (i, j) = (
+        (x.floor() as usize).min(columns.saturating_sub(2)),
+        (y.floor() as usize).min(rows.saturating_sub(2)),
+    );
+    let (tx, ty) = (x - i as f32, y - j as f32);
+    let (next_i, next_j) = ((i + 1).min(columns - 1), (j + 1).min(rows - 1));
+
+    let lower = field[i + j * columns] * (1. - tx) + field[next_i + j * columns] * tx;
+    let upper = field[i + next_j * columns] * (1. - tx) + field[next_i + next_j * columns] * tx;
+    lower * (1. - ty) + upper * ty
+}
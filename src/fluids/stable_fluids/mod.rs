@@ -0,0 +1,127 @@
+mod tests;
+
+pub mod advection;
+pub mod diffusion;
+pub mod mac_grid;
+pub mod projection;
+pub mod render;
+
+use bevy::prelude::*;
+
+use crate::kinetics::{
+    bounds::{MAX_X, MAX_Y, MIN_X, MIN_Y},
+    velocity::PIXELS_PER_METER,
+    Solver,
+};
+use mac_grid::MacGrid;
+
+/// Grid based smoke and dye simulation (Stam's stable fluids on a MAC grid)
+/// covering the same domain as the particles.
+pub struct StableFluidsPlugin;
+
+impl Plugin for StableFluidsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(StableFluidsSettings::default())
+            .add_systems(
+                Startup,
+                (init_grid, render::spawn_dye_texture)
+                    .chain()
+                    .run_if(resource_equals(Solver::StableFluids)),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    add_inflow,
+                    advection::advect_velocity,
+                    diffusion::diffuse_velocity,
+                    projection::project,
+                    advection::advect_dye,
+                    diffusion::diffuse_dye,
+                )
+                    .chain()
+                    .run_if(resource_equals(Solver::StableFluids)),
+            )
+            .add_systems(
+                Update,
+                render::update_dye_texture.run_if(resource_equals(Solver::StableFluids)),
+            );
+    }
+}
+
+/// Lengths and positions are in pixels, everything else is in SI units.
+#[derive(Resource, Clone, Copy)]
+pub struct StableFluidsSettings {
+    pub cell_size: f32,
+    pub viscosity: f32,
+    pub dye_diffusion: f32,
+    /// Fraction of the dye that fades away every second.
+    pub dye_dissipation: f32,
+    pub diffusion_iterations: usize,
+    pub pressure_iterations: usize,
+    pub inflow_center: Vec2,
+    pub inflow_radius: f32,
+    pub inflow_velocity: Vec2,
+    /// Dye concentration added to the inflow region every second.
+    pub inflow_dye_rate: f32,
+}
+
+impl Default for StableFluidsSettings {
+    fn default() -> Self {
+        StableFluidsSettings {
+            cell_size: 4.,
+            viscosity: 0.0001,
+            dye_diffusion: 0.,
+            dye_dissipation: 0.05,
+            diffusion_iterations: 10,
+            pressure_iterations: 40,
+            inflow_center: Vec2::new((MIN_X + MAX_X) / 2., MIN_Y + 30.),
+            inflow_radius: 12.,
+            inflow_velocity: Vec2::new(0., 2.),
+            inflow_dye_rate: 4.,
+        }
+    }
+}
+
+fn init_grid(mut commands: Commands, stable_fluids_settings: Res<StableFluidsSettings>) {
+    let width = ((MAX_X - MIN_X) / stable_fluids_settings.cell_size) as usize;
+    let height = ((MAX_Y - MIN_Y) / stable_fluids_settings.cell_size) as usize;
+    commands.insert_resource(MacGrid::new(
+        width,
+        height,
+        stable_fluids_settings.cell_size / PIXELS_PER_METER,
+    ));
+}
+
+/// Converts a position in pixels into grid space.
+pub fn to_grid_position(position: Vec2) -> Vec2 {
+    (position - Vec2::new(MIN_X, MIN_Y)) / PIXELS_PER_METER
+}
+
+fn add_inflow(
+    time: Res<Time>,
+    stable_fluids_settings: Res<StableFluidsSettings>,
+    mut grid: ResMut<MacGrid>,
+) {
+    let delta = time.delta().as_secs_f32();
+    let center = to_grid_position(stable_fluids_settings.inflow_center);
+    let radius = stable_fluids_settings.inflow_radius / PIXELS_PER_METER;
+    let velocity = stable_fluids_settings.inflow_velocity;
+
+    for j in 0..grid.height {
+        for i in 0..grid.width {
+            if grid.cell_center(i, j).distance(center) <= radius {
+                let idx = grid.cell_idx(i, j);
+                grid.dye[idx] =
+                    (grid.dye[idx] + stable_fluids_settings.inflow_dye_rate * delta).min(1.);
+            }
+            if grid.u_position(i, j).distance(center) <= radius {
+                let idx = grid.u_idx(i, j);
+                grid.u[idx] = velocity.x;
+            }
+            if grid.v_position(i, j).distance(center) <= radius {
+                let idx = grid.v_idx(i, j);
+                grid.v[idx] = velocity.y;
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use bevy::prelude::*;
+
+use super::{mac_grid::MacGrid, StableFluidsSettings};
+
+/// Makes the velocity field divergence free.
+pub fn project(
+    time: Res<Time>,
+    stable_fluids_settings: Res<StableFluidsSettings>,
+    mut grid: ResMut<MacGrid>,
+) {
+    let fluid_cells = vec![true; grid.width * grid.height];
+    project_velocities(
+        &mut grid,
+        time.delta().as_secs_f32(),
+        stable_fluids_settings.pressure_iterations,
+        &fluid_cells,
+    );
+}
+
+/// Solves the pressure Poisson equation with Gauss-Seidel iterations and
+/// subtracts the pressure gradient from the face velocities.
+/// Cells that are not marked in `fluid_cells` are treated as empty (zero
+/// pressure), the domain edges as solid walls.
+pub fn project_velocities(grid: &mut MacGrid, delta: f32, iterations: usize, fluid_cells: &[bool]) {
+    if delta == 0. {
+        return;
+    }
+    let (width, height, cell_size) = (grid.width, grid.height, grid.cell_size);
+    enforce_solid_walls(grid);
+
+    let divergence: Vec<f32> = (0..width * height)
+        .map(|idx| {
+            let (i, j) = (idx % width, idx / width);
+            (grid.u[grid.u_idx(i + 1, j)] - grid.u[grid.u_idx(i, j)] + grid.v[grid.v_idx(i, j + 1)]
+                - grid.v[grid.v_idx(i, j)])
+                / cell_size
+        })
+        .collect();
+
+    let mut pressure = vec![0.; width * height];
+    for _ in 0..iterations {
+        for j in 0..height {
+            for i in 0..width {
+                let idx = grid.cell_idx(i, j);
+                if !fluid_cells[idx] {
+                    continue;
+                }
+                let mut neighbours_sum = 0.;
+                let mut amount_of_neighbours = 0.;
+                if i > 0 {
+                    neighbours_sum += pressure[idx - 1];
+                    amount_of_neighbours += 1.;
+                }
+                if i + 1 < width {
+                    neighbours_sum += pressure[idx + 1];
+                    amount_of_neighbours += 1.;
+                }
+                if j > 0 {
+                    neighbours_sum += pressure[idx - width];
+                    amount_of_neighbours += 1.;
+                }
+                if j + 1 < height {
+                    neighbours_sum += pressure[idx + width];
+                    amount_of_neighbours += 1.;
+                }
+                pressure[idx] = (neighbours_sum - cell_size * cell_size * divergence[idx] / delta)
+                    / amount_of_neighbours;
+            }
+        }
+    }
+
+    let scale = delta / cell_size;
+    for j in 0..height {
+        for i in 1..width {
+            let (left, right) = (grid.cell_idx(i - 1, j), grid.cell_idx(i, j));
+            if fluid_cells[left] || fluid_cells[right] {
+                let idx = grid.u_idx(i, j);
+                grid.u[idx] -= scale * (pressure[right] - pressure[left]);
+            }
+        }
+    }
+    for j in 1..height {
+        for i in 0..width {
+            let (down, up) = (grid.cell_idx(i, j - 1), grid.cell_idx(i, j));
+            if fluid_cells[down] || fluid_cells[up] {
+                let idx = grid.v_idx(i, j);
+                grid.v[idx] -= scale * (pressure[up] - pressure[down]);
+            }
+        }
+    }
+}
+
+fn enforce_solid_walls(grid: &mut MacGrid) {
+    for j in 0..grid.height {
+        let (left, right) = (grid.u_idx(0, j), grid.u_idx(grid.width, j));
+        grid.u[left] = 0.;
+        grid.u[right] = 0.;
+    }
+    for i in 0..grid.width {
+        let (down, up) = (grid.v_idx(i, 0), grid.v_idx(i, grid.height));
+        grid.v[down] = 0.;
+        grid.v[up] = 0.;
+    }
+}
@@ -0,0 +1,60 @@
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+
+use super::mac_grid::MacGrid;
+use crate::kinetics::bounds::{MAX_X, MAX_Y, MIN_X, MIN_Y};
+
+#[derive(Resource)]
+pub struct DyeTexture(Handle<Image>);
+
+pub fn spawn_dye_texture(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    grid: Res<MacGrid>,
+) {
+    let image = Image::new_fill(
+        Extent3d {
+            width: grid.width as u32,
+            height: grid.height as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+    );
+    let handle = images.add(image);
+
+    commands.spawn((
+        Sprite {
+            image: handle.clone(),
+            custom_size: Some(Vec2::new(MAX_X - MIN_X, MAX_Y - MIN_Y)),
+            ..default()
+        },
+        Transform::from_xyz((MIN_X + MAX_X) / 2., (MIN_Y + MAX_Y) / 2., -0.5),
+    ));
+    commands.insert_resource(DyeTexture(handle));
+}
+
+pub fn update_dye_texture(
+    grid: Res<MacGrid>,
+    dye_texture: Res<DyeTexture>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let Some(image) = images.get_mut(&dye_texture.0) else {
+        return;
+    };
+    // Image rows go from the top down, grid rows from the bottom up.
+    for (row, pixels) in image.data.chunks_exact_mut(grid.width * 4).enumerate() {
+        let j = grid.height - 1 - row;
+        for (i, pixel) in pixels.chunks_exact_mut(4).enumerate() {
+            let dye = grid.dye[grid.cell_idx(i, j)].clamp(0., 1.);
+            pixel.copy_from_slice(&[255, 255, 255, (dye * 255.) as u8]);
+        }
+    }
+}
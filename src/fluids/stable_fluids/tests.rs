@@ -0,0 +1,61 @@
+#[cfg(test)]
+mod stable_fluids_tests {
+    use std::time::Duration;
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::super::{
+        advection::advect_velocity, mac_grid::MacGrid, projection::project_velocities, *,
+    };
+
+    const DELTA: f32 = 1. / 60.;
+
+    fn max_divergence(grid: &MacGrid) -> f32 {
+        (0..grid.height)
+            .flat_map(|j| (0..grid.width).map(move |i| (i, j)))
+            .map(|(i, j)| {
+                ((grid.u[grid.u_idx(i + 1, j)] - grid.u[grid.u_idx(i, j)]
+                    + grid.v[grid.v_idx(i, j + 1)]
+                    - grid.v[grid.v_idx(i, j)])
+                    / grid.cell_size)
+                    .abs()
+            })
+            .fold(0., f32::max)
+    }
+
+    #[test]
+    fn projection_removes_the_divergence() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut grid = MacGrid::new(16, 16, 0.2);
+        grid.u.iter_mut().for_each(|u| *u = rng.gen_range(-1.0..1.));
+        grid.v.iter_mut().for_each(|v| *v = rng.gen_range(-1.0..1.));
+        let initial_divergence = max_divergence(&grid);
+
+        let fluid_cells = vec![true; grid.width * grid.height];
+        project_velocities(&mut grid, DELTA, 1000, &fluid_cells);
+
+        let divergence = max_divergence(&grid);
+        assert!(
+            divergence < 1e-3 * initial_divergence,
+            "{divergence} {initial_divergence}"
+        );
+    }
+
+    #[test]
+    fn advection_leaves_a_uniform_field_unchanged() {
+        let mut world = World::new();
+        let mut time = Time::<()>::default();
+        time.advance_by(Duration::from_secs_f32(DELTA));
+        world.insert_resource(time);
+        let mut grid = MacGrid::new(16, 16, 0.2);
+        grid.u.fill(1.5);
+        grid.v.fill(-0.5);
+        world.insert_resource(grid);
+
+        world.run_system_cached(advect_velocity).unwrap();
+
+        let grid = world.resource::<MacGrid>();
+        assert!(grid.u.iter().all(|&u| (u - 1.5).abs() < 1e-6));
+        assert!(grid.v.iter().all(|&v| (v + 0.5).abs() < 1e-6));
+    }
+}
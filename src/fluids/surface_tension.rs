@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+
+use super::{
+    kernels,
+    kinds::{FluidKind, FluidKinds},
+    particle::FluidParticle,
+    SphSettings,
+};
+use crate::kinetics::{
+    bounds::periodic::Periodicity,
+    collisions::position_hashing::PositionHashMap,
+    forces::{ForceSource, Forces},
+    mass::Mass,
+    velocity::PIXELS_PER_METER,
+};
+
+/// Pulls every particle towards its neighbours by the cohesion between their
+/// kinds, pushes it away where that is negative. Inside a single phase the
+/// pulls cancel out and only the surface is held together; between two
+/// phases that repel, the interface stays sharp.
+pub fn apply_cohesion_forces(
+    sph_settings: Res<SphSettings>,
+    fluid_kinds: Res<FluidKinds>,
+    position_hash_map: Res<PositionHashMap>,
+    periodicity: Res<Periodicity>,
+    mut particles_q: Query<
+        (Entity, &FluidKind, &Transform, &Mass, &mut Forces),
+        With<FluidParticle>,
+    >,
+    neighbours_q: Query<(&FluidKind, &Transform, &Mass), With<FluidParticle>>,
+) {
+    if !fluid_kinds.has_cohesion() {
+        return;
+    }
+    let smoothing_radius = sph_settings.smoothing_radius_in_meters();
+    particles_q
+        .par_iter_mut()
+        .for_each(|(entity, kind, transform, Mass(mass), mut forces)| {
+            let center = transform.translation.xy();
+
+            let cohesion_force: Vec2 = position_hash_map
+                .entities_near(center, sph_settings.smoothing_radius)
+                .into_iter()
+                .filter(|neighbour| *neighbour != entity)
+                .filter_map(|neighbour| neighbours_q.get(neighbour).ok())
+                .map(
+                    |(neighbour_kind, neighbour_transform, Mass(neighbour_mass))| {
+                        let offset = periodicity
+                            .offset(neighbour_transform.translation.xy(), center)
+                            / PIXELS_PER_METER;
+                        -fluid_kinds.cohesion(*kind, *neighbour_kind)
+                            * mass
+                            * neighbour_mass
+                            * kernels::poly6(offset.length_squared(), smoothing_radius)
+                            * offset.normalize_or_zero()
+                    },
+                )
+                .sum();
+
+            if cohesion_force != Vec2::ZERO {
+                forces.add(ForceSource::SurfaceTension, cohesion_force);
+            }
+        });
+}
@@ -0,0 +1,86 @@
+use bevy::prelude::*;
+
+use super::{
+    density::Density,
+    kernels,
+    kinds::{FluidKind, FluidKinds},
+    particle::FluidParticle,
+    SphSettings,
+};
+use crate::kinetics::{
+    bounds::periodic::Periodicity,
+    collisions::position_hashing::PositionHashMap,
+    forces::{ForceSource, Forces},
+    mass::Mass,
+    velocity::{Velocity, PIXELS_PER_METER},
+};
+
+type ViscousParticles<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static FluidKind,
+        &'static Transform,
+        &'static Mass,
+        &'static Density,
+        &'static Velocity,
+        &'static mut Forces,
+    ),
+    With<FluidParticle>,
+>;
+
+pub fn apply_viscosity_forces(
+    sph_settings: Res<SphSettings>,
+    fluid_kinds: Res<FluidKinds>,
+    position_hash_map: Res<PositionHashMap>,
+    periodicity: Res<Periodicity>,
+    mut particles_q: ViscousParticles,
+    neighbours_q: Query<(&FluidKind, &Transform, &Mass, &Density, &Velocity), With<FluidParticle>>,
+) {
+    let smoothing_radius = sph_settings.smoothing_radius_in_meters();
+    particles_q.par_iter_mut().for_each(
+        |(entity, kind, transform, Mass(mass), density, Velocity(velocity), mut forces)| {
+            if density.0 <= 0. {
+                return;
+            }
+            let center = transform.translation.xy();
+
+            let viscosity_force: Vec2 = position_hash_map
+                .entities_near(center, sph_settings.smoothing_radius)
+                .into_iter()
+                .filter(|neighbour| *neighbour != entity)
+                .filter_map(|neighbour| neighbours_q.get(neighbour).ok())
+                .filter(|(_, _, _, Density(neighbour_density), _)| *neighbour_density > 0.)
+                .map(
+                    |(
+                        neighbour_kind,
+                        neighbour_transform,
+                        Mass(neighbour_mass),
+                        Density(neighbour_density),
+                        Velocity(neighbour_velocity),
+                    )| {
+                        let distance = periodicity
+                            .offset(neighbour_transform.translation.xy(), center)
+                            .length()
+                            / PIXELS_PER_METER;
+                        fluid_kinds.viscosity(*kind, *neighbour_kind)
+                            * neighbour_mass
+                            * (neighbour_velocity - velocity)
+                            / neighbour_density
+                            * kernels::viscosity_laplacian(distance, smoothing_radius)
+                    },
+                )
+                .sum();
+
+            if viscosity_force != Vec2::ZERO {
+                // The sum is a force per unit of volume, taken over the
+                // volume of the particle.
+                forces.add(
+                    ForceSource::Viscosity,
+                    viscosity_force / density.number_density(*mass),
+                );
+            }
+        },
+    );
+}
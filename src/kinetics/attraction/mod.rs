@@ -0,0 +1,70 @@
+pub mod quadtree;
+mod tests;
+
+use bevy::prelude::*;
+
+use super::{
+    forces::{ForceSource, Forces},
+    mass::Mass,
+    velocity::PIXELS_PER_METER,
+};
+use crate::fluids::particle::FluidParticle;
+use quadtree::{AttractingBody, QuadTree};
+
+/// Parameters of the mutual attraction between the particles.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct AttractionSettings {
+    /// In m³/(kg s²).
+    pub gravitational_constant: f32,
+    /// Largest ratio between the size of a quadtree node and its distance
+    /// for it to attract as a single body. Zero makes it exact but O(N²).
+    pub opening_angle: f32,
+    /// Distance, in pixels, added to every separation so that close
+    /// particles don't fling each other away.
+    pub softening_length: f32,
+}
+
+impl Default for AttractionSettings {
+    fn default() -> Self {
+        AttractionSettings {
+            gravitational_constant: 0.05,
+            opening_angle: 0.5,
+            softening_length: 8.,
+        }
+    }
+}
+
+/// Pulls every particle towards all the others with Newtonian gravity,
+/// approximated in O(N log N) with a Barnes-Hut quadtree. Distances are
+/// taken inside the domain, not through the periodic sides.
+pub fn apply_attraction(
+    attraction_settings: Res<AttractionSettings>,
+    mut particles_q: Query<(Entity, &Transform, &Mass, &mut Forces), With<FluidParticle>>,
+) {
+    let quadtree = QuadTree::new(
+        particles_q
+            .iter()
+            .map(|(entity, transform, Mass(mass), _)| AttractingBody {
+                entity,
+                position: transform.translation.xy() / PIXELS_PER_METER,
+                mass: *mass,
+            }),
+    );
+    let AttractionSettings {
+        gravitational_constant,
+        opening_angle,
+        softening_length,
+    } = *attraction_settings;
+    particles_q
+        .par_iter_mut()
+        .for_each(|(entity, transform, Mass(mass), mut forces)| {
+            let acceleration = quadtree.acceleration_at(
+                entity,
+                transform.translation.xy() / PIXELS_PER_METER,
+                gravitational_constant,
+                opening_angle,
+                softening_length / PIXELS_PER_METER,
+            );
+            forces.add(ForceSource::Gravity, mass * acceleration);
+        });
+}
@@ -0,0 +1,191 @@
+use std::ops::Range;
+
+use bevy::prelude::*;
+
+/// Body whose attraction is approximated by the `QuadTree`. Positions are in
+/// meters.
+#[derive(Clone, Copy, Debug)]
+pub struct AttractingBody {
+    pub entity: Entity,
+    pub position: Vec2,
+    pub mass: f32,
+}
+
+/// Barnes-Hut quadtree: every node is a square holding the total mass and
+/// the center of mass of the bodies inside it, so that a far enough node
+/// attracts as a single body.
+#[derive(Default)]
+pub struct QuadTree {
+    /// Sorted so that the bodies of a node are contiguous.
+    bodies: Vec<AttractingBody>,
+    /// The root first, the children of a node next to each other.
+    nodes: Vec<Node>,
+}
+
+struct Node {
+    /// Corner with the smallest coordinates.
+    min: Vec2,
+    size: f32,
+    mass: f32,
+    center_of_mass: Vec2,
+    /// Range in `QuadTree::bodies`.
+    bodies: Range<usize>,
+    /// Range in `QuadTree::nodes`, empty for the leaves.
+    children: Range<usize>,
+}
+
+/// Bodies a leaf holds at most, past which it is split.
+const LEAF_CAPACITY: usize = 4;
+
+/// Depth past which nodes are not split anymore, so that bodies at the same
+/// position end up in a leaf instead of splitting it forever.
+const MAX_DEPTH: usize = 24;
+
+impl QuadTree {
+    /// The bodies are sorted by entity first, so that the tree and the sums
+    /// made over it do not depend on the order they come in.
+    pub fn new(bodies: impl IntoIterator<Item = AttractingBody>) -> QuadTree {
+        let mut bodies: Vec<AttractingBody> = bodies
+            .into_iter()
+            .filter(|body| body.position.is_finite())
+            .collect();
+        bodies.sort_unstable_by_key(|body| body.entity);
+        let mut tree = QuadTree {
+            bodies,
+            nodes: vec![],
+        };
+        if tree.bodies.is_empty() {
+            return tree;
+        }
+
+        let (min, max) = tree
+            .bodies
+            .iter()
+            .fold((Vec2::INFINITY, Vec2::NEG_INFINITY), |(min, max), body| {
+                (min.min(body.position), max.max(body.position))
+            });
+        // Slightly bigger than the bodies so the ones on the far sides are
+        // strictly inside.
+        let size = (max - min).max_element().max(f32::EPSILON) * (1. + 1e-4);
+        tree.nodes.push(tree.node(min, size, 0..tree.bodies.len()));
+        tree.split(0, 0);
+        tree
+    }
+
+    fn node(&self, min: Vec2, size: f32, bodies: Range<usize>) -> Node {
+        let (mass, weighted_position) = self.bodies[bodies.clone()].iter().fold(
+            (0., Vec2::ZERO),
+            |(mass, weighted_position), body| {
+                (
+                    mass + body.mass,
+                    weighted_position + body.mass * body.position,
+                )
+            },
+        );
+        let center_of_mass = if mass > 0. {
+            weighted_position / mass
+        } else {
+            min + size / 2.
+        };
+        Node {
+            min,
+            size,
+            mass,
+            center_of_mass,
+            bodies,
+            children: 0..0,
+        }
+    }
+
+    /// Splits the node at `idx` in quadrants, down to the leaves.
+    fn split(&mut self, idx: usize, depth: usize) {
+        let Node {
+            min, size, bodies, ..
+        } = &self.nodes[idx];
+        let (min, size, bodies) = (*min, *size, bodies.clone());
+        if bodies.len() <= LEAF_CAPACITY || depth >= MAX_DEPTH {
+            return;
+        }
+
+        let half_size = size / 2.;
+        let middle = min + half_size;
+        let quadrant_of = |body: &AttractingBody| {
+            (body.position.x >= middle.x) as usize + 2 * (body.position.y >= middle.y) as usize
+        };
+        // Stable, so the bodies stay sorted by entity within every quadrant.
+        self.bodies[bodies.clone()].sort_by_key(quadrant_of);
+
+        let first_child = self.nodes.len();
+        let mut start = bodies.start;
+        for quadrant in 0..4 {
+            let end = start
+                + self.bodies[start..bodies.end]
+                    .iter()
+                    .take_while(|body| quadrant_of(body) == quadrant)
+                    .count();
+            if end > start {
+                let quadrant_min =
+                    min + Vec2::new((quadrant % 2) as f32, (quadrant / 2) as f32) * half_size;
+                let child = self.node(quadrant_min, half_size, start..end);
+                self.nodes.push(child);
+            }
+            start = end;
+        }
+        let children = first_child..self.nodes.len();
+        self.nodes[idx].children = children.clone();
+        for child in children {
+            self.split(child, depth + 1);
+        }
+    }
+
+    /// Acceleration, in m/s², of a body of `entity` at `position` due to all
+    /// the other bodies. Nodes seen under an angle smaller than
+    /// `opening_angle` count as a single body, and `softening_length` keeps
+    /// the attraction finite between close bodies.
+    pub fn acceleration_at(
+        &self,
+        entity: Entity,
+        position: Vec2,
+        gravitational_constant: f32,
+        opening_angle: f32,
+        softening_length: f32,
+    ) -> Vec2 {
+        let softening_squared = softening_length * softening_length;
+        let attraction = |mass: f32, other_position: Vec2| {
+            let offset = other_position - position;
+            let distance_squared = offset.length_squared() + softening_squared;
+            if distance_squared == 0. {
+                return Vec2::ZERO;
+            }
+            offset * (gravitational_constant * mass / (distance_squared * distance_squared.sqrt()))
+        };
+
+        let mut acceleration = Vec2::ZERO;
+        let mut stack = if self.nodes.is_empty() {
+            vec![]
+        } else {
+            vec![0]
+        };
+        while let Some(idx) = stack.pop() {
+            let node = &self.nodes[idx];
+            let is_inside =
+                position.cmpge(node.min).all() && position.cmplt(node.min + node.size).all();
+            let distance_squared = node.center_of_mass.distance_squared(position);
+            if !is_inside
+                && node.size * node.size < opening_angle * opening_angle * distance_squared
+            {
+                acceleration += attraction(node.mass, node.center_of_mass);
+            } else if node.children.is_empty() {
+                for body in &self.bodies[node.bodies.clone()] {
+                    if body.entity != entity {
+                        acceleration += attraction(body.mass, body.position);
+                    }
+                }
+            } else {
+                // In reverse so that the children are visited in order.
+                stack.extend(node.children.clone().rev());
+            }
+        }
+        acceleration
+    }
+}
@@ -0,0 +1,85 @@
+#[cfg(test)]
+mod quadtree_tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::super::quadtree::*;
+    use bevy::prelude::*;
+
+    const G: f32 = 0.05;
+    const SOFTENING: f32 = 0.1;
+
+    fn random_bodies(amount: u32) -> Vec<AttractingBody> {
+        let mut rng = StdRng::seed_from_u64(40);
+        (0..amount)
+            .map(|idx| AttractingBody {
+                entity: Entity::from_raw(idx),
+                position: Vec2::new(rng.gen_range(-5.0..5.), rng.gen_range(-5.0..5.)),
+                mass: rng.gen_range(0.5..2.),
+            })
+            .collect()
+    }
+
+    fn brute_force_acceleration(bodies: &[AttractingBody], body: &AttractingBody) -> Vec2 {
+        bodies
+            .iter()
+            .filter(|other| other.entity != body.entity)
+            .map(|other| {
+                let offset = other.position - body.position;
+                let distance_squared = offset.length_squared() + SOFTENING * SOFTENING;
+                offset * G * other.mass / distance_squared.powf(1.5)
+            })
+            .sum()
+    }
+
+    /// Error over all the bodies, relative to the accelerations. Per body it
+    /// is large where the attractions almost cancel out.
+    fn relative_error(bodies: &[AttractingBody], opening_angle: f32) -> f32 {
+        let quadtree = QuadTree::new(bodies.iter().copied());
+        let (error, total) = bodies.iter().fold((0., 0.), |(error, total), body| {
+            let expected = brute_force_acceleration(bodies, body);
+            let approximated =
+                quadtree.acceleration_at(body.entity, body.position, G, opening_angle, SOFTENING);
+            (
+                error + (approximated - expected).length(),
+                total + expected.length(),
+            )
+        });
+        error / total
+    }
+
+    #[test]
+    fn closed_tree_matches_the_pairwise_sum() {
+        assert!(relative_error(&random_bodies(300), 0.) < 1e-4);
+    }
+
+    #[test]
+    fn opening_angle_trades_accuracy_for_speed() {
+        let bodies = random_bodies(2000);
+        let coarse = relative_error(&bodies, 0.8);
+        let fine = relative_error(&bodies, 0.3);
+        assert!(fine < coarse);
+        assert!(fine < 0.01, "{fine}");
+        assert!(coarse < 0.05, "{coarse}");
+    }
+
+    #[test]
+    fn bodies_at_the_same_position_attract_the_others_but_not_each_other() {
+        let bodies: Vec<AttractingBody> = (0..10)
+            .map(|idx| AttractingBody {
+                entity: Entity::from_raw(idx),
+                position: if idx == 0 {
+                    Vec2::new(3., 0.)
+                } else {
+                    Vec2::ZERO
+                },
+                mass: 1.,
+            })
+            .collect();
+        let quadtree = QuadTree::new(bodies.iter().copied());
+        let stacked = quadtree.acceleration_at(Entity::from_raw(1), Vec2::ZERO, G, 0.5, SOFTENING);
+        let lone = quadtree.acceleration_at(Entity::from_raw(0), Vec2::new(3., 0.), G, 0.5, 0.);
+        assert!(stacked.x > 0. && stacked.y == 0.);
+        // Nine unit masses three meters away.
+        assert!((lone - Vec2::new(-G, 0.)).length() < 1e-5, "{lone}");
+    }
+}
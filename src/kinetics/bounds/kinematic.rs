@@ -0,0 +1,135 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use super::Boundary;
+use crate::kinetics::velocity::PIXELS_PER_METER;
+
+/// Position, in pixels, and rotation, in radians, of a boundary.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Pose {
+    pub translation: Vec2,
+    pub rotation: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Keyframe {
+    /// Seconds since the start of the loop.
+    pub time: f32,
+    pub pose: Pose,
+}
+
+/// How a boundary entity moves over time. The walls push the particles they
+/// meet, but nothing pushes them back.
+#[derive(Component, Clone, Debug)]
+pub enum BoundaryMotion {
+    Static,
+    /// Poses linearly interpolated between keyframes sorted by time, looping
+    /// back to the first one after the last.
+    Keyframes(Vec<Keyframe>),
+    /// Pose as a function of the elapsed time in seconds.
+    Function(fn(f32) -> Pose),
+}
+
+impl BoundaryMotion {
+    /// Tilts the container one way, then the other.
+    pub fn tilt() -> BoundaryMotion {
+        let pose = |rotation: f32| Pose {
+            translation: Vec2::ZERO,
+            rotation,
+        };
+        BoundaryMotion::Keyframes(vec![
+            Keyframe {
+                time: 0.,
+                pose: pose(0.),
+            },
+            Keyframe {
+                time: 2.,
+                pose: pose(0.25),
+            },
+            Keyframe {
+                time: 4.,
+                pose: pose(-0.25),
+            },
+            Keyframe {
+                time: 6.,
+                pose: pose(0.),
+            },
+        ])
+    }
+
+    /// Shakes the container sideways while rocking it.
+    pub fn slosh() -> BoundaryMotion {
+        BoundaryMotion::Function(|time| Pose {
+            translation: Vec2::new(20. * (TAU * 0.5 * time).sin(), 0.),
+            rotation: 0.08 * (TAU * 0.5 * time).cos(),
+        })
+    }
+
+    pub fn pose_at(&self, time: f32) -> Option<Pose> {
+        match self {
+            BoundaryMotion::Static => None,
+            BoundaryMotion::Function(pose_at) => Some(pose_at(time)),
+            BoundaryMotion::Keyframes(keyframes) => {
+                let period = keyframes.last()?.time;
+                let time = if period > 0. { time % period } else { 0. };
+                let next = keyframes
+                    .iter()
+                    .position(|keyframe| keyframe.time > time)
+                    .unwrap_or(keyframes.len() - 1);
+                let Some(previous) = next.checked_sub(1).map(|idx| keyframes[idx]) else {
+                    return Some(keyframes[next].pose);
+                };
+                let next = keyframes[next];
+                let t = ((time - previous.time) / (next.time - previous.time)).clamp(0., 1.);
+                Some(Pose {
+                    translation: previous.pose.translation.lerp(next.pose.translation, t),
+                    rotation: previous.pose.rotation
+                        + (next.pose.rotation - previous.pose.rotation) * t,
+                })
+            }
+        }
+    }
+}
+
+/// Velocity of a moving boundary, linear in m/s and angular in rad/s around
+/// its `Transform` translation.
+#[derive(Component, Clone, Copy, Default, Debug)]
+pub struct KinematicVelocity {
+    pub linear: Vec2,
+    pub angular: f32,
+}
+
+impl KinematicVelocity {
+    /// Velocity, in m/s, of the boundary at `offset` pixels from its center.
+    pub fn at(&self, offset: Vec2) -> Vec2 {
+        self.linear + self.angular * offset.perp() / PIXELS_PER_METER
+    }
+}
+
+pub fn move_boundaries(
+    time: Res<Time>,
+    mut boundaries_q: Query<
+        (Ref<BoundaryMotion>, &mut Transform, &mut KinematicVelocity),
+        With<Boundary>,
+    >,
+) {
+    let delta = time.delta().as_secs_f32();
+    for (motion, mut transform, mut velocity) in boundaries_q.iter_mut() {
+        *velocity = KinematicVelocity::default();
+        let Some(pose) = motion.pose_at(time.elapsed_secs()) else {
+            continue;
+        };
+        let previous_rotation = transform.rotation.to_euler(EulerRot::XYZ).2;
+        // A motion that was just switched to jumps to its first pose.
+        if delta > 0. && !motion.is_changed() {
+            *velocity = KinematicVelocity {
+                linear: (pose.translation - transform.translation.xy())
+                    / (delta * PIXELS_PER_METER),
+                angular: (pose.rotation - previous_rotation) / delta,
+            };
+        }
+        transform.translation = pose.translation.extend(transform.translation.z);
+        transform.rotation = Quat::from_rotation_z(pose.rotation);
+    }
+}
@@ -0,0 +1,315 @@
+pub mod kinematic;
+pub mod periodic;
+pub mod sdf;
+mod tests;
+
+use core::f32;
+
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+
+use crate::{
+    fluids::particle::FluidParticle,
+    kinetics::{mass::Mass, obstacles::shape::ObstacleShape, velocity::Velocity},
+};
+use kinematic::{BoundaryMotion, KinematicVelocity};
+use periodic::Periodicity;
+use sdf::Sdf;
+
+use super::forces::{ForceSource, Forces};
+
+/// Shape of the container holding the particles. At rest it fits in the
+/// `MIN_X..MAX_X`, `MIN_Y..MAX_Y` domain, which also stops the particles.
+#[derive(Resource, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Container {
+    /// The whole domain.
+    #[default]
+    Box,
+    /// Wide reservoir draining through a spout into a basin.
+    Funnel,
+    /// Two columns joined at the bottom by a half ring.
+    UTube,
+    /// Half disk open at the top.
+    Bowl,
+}
+
+/// Walls of a container, moved by its `Transform`.
+#[derive(Component)]
+pub struct Boundary {
+    /// Relative to the `Transform`, negative where the particles are allowed
+    /// to be.
+    pub sdf: Sdf,
+}
+
+impl Boundary {
+    pub fn new(container: Container) -> Boundary {
+        let (min, max) = (Vec2::new(MIN_X, MIN_Y), Vec2::new(MAX_X, MAX_Y));
+        let sdf = match container {
+            Container::Box => Sdf::rectangle(min, max),
+            Container::Funnel => Sdf::rectangle(Vec2::new(-190., 50.), Vec2::new(190., 195.))
+                .union(Sdf::shape(
+                    ObstacleShape::Polygon {
+                        vertices: vec![
+                            Vec2::new(-190., 55.),
+                            Vec2::new(-15., -65.),
+                            Vec2::new(15., -65.),
+                            Vec2::new(190., 55.),
+                        ],
+                    },
+                    Vec2::ZERO,
+                ))
+                .union(Sdf::rectangle(Vec2::new(-15., -150.), Vec2::new(15., -60.)))
+                .union(Sdf::rectangle(
+                    Vec2::new(-190., -195.),
+                    Vec2::new(190., -145.),
+                ))
+                .sampled(min, max, 4.),
+            Container::UTube => {
+                let ring = Sdf::shape(ObstacleShape::Circle { radius: 160. }, Vec2::new(0., -30.))
+                    .subtract(Sdf::shape(
+                        ObstacleShape::Circle { radius: 80. },
+                        Vec2::new(0., -30.),
+                    ))
+                    .subtract(Sdf::rectangle(
+                        Vec2::new(-200., -30.),
+                        Vec2::new(200., 200.),
+                    ));
+                ring.union(Sdf::rectangle(
+                    Vec2::new(-160., -40.),
+                    Vec2::new(-80., 195.),
+                ))
+                .union(Sdf::rectangle(Vec2::new(80., -40.), Vec2::new(160., 195.)))
+                .sampled(min, max, 4.)
+            }
+            Container::Bowl => {
+                Sdf::shape(ObstacleShape::Circle { radius: 180. }, Vec2::new(0., 15.))
+                    .subtract(Sdf::rectangle(Vec2::new(-200., 15.), Vec2::new(200., 200.)))
+                    .union(Sdf::rectangle(Vec2::new(-180., 10.), Vec2::new(180., 195.)))
+                    .sampled(min, max, 4.)
+            }
+        };
+        Boundary { sdf }
+    }
+
+    /// `position` in the frame of the boundary placed by `transform`. Along
+    /// periodic axes the walls are looked up on the center line of the
+    /// container, where there are none.
+    fn local_point(transform: &Transform, position: Vec2, periodicity: &Periodicity) -> Vec2 {
+        let offset = position - transform.translation.xy();
+        let local_point = (transform.rotation.inverse() * offset.extend(0.)).xy();
+        Vec2::select(
+            BVec2::new(periodicity.x, periodicity.y),
+            Vec2::ZERO,
+            local_point,
+        )
+    }
+
+    /// How deep a particle of `radius` at `position` is in the walls of the
+    /// boundary placed by `transform`, with the outward normal of the wall,
+    /// or `None` if it doesn't touch them.
+    pub fn penetration(
+        &self,
+        transform: &Transform,
+        position: Vec2,
+        radius: f32,
+        periodicity: &Periodicity,
+    ) -> Option<(f32, Vec2)> {
+        let local_point = Boundary::local_point(transform, position, periodicity);
+        let penetration = self.sdf.distance(local_point) + radius;
+        if penetration <= 0. {
+            return None;
+        }
+        let normal = (transform.rotation * self.sdf.gradient(local_point).extend(0.)).xy();
+        Some((penetration, normal))
+    }
+
+    /// Fraction of `displacement` after which a particle of `radius` leaving
+    /// `position` touches the walls of the boundary placed by `transform`,
+    /// with the outward normal of the wall there. The path is sphere traced:
+    /// the particle advances by its distance to the walls until it touches
+    /// them. Particles that already overlap the walls are left to
+    /// `enforce_bounds`.
+    pub fn time_of_impact(
+        &self,
+        transform: &Transform,
+        position: Vec2,
+        displacement: Vec2,
+        radius: f32,
+        periodicity: &Periodicity,
+    ) -> Option<(f32, Vec2)> {
+        let length = displacement.length();
+        if length == 0. {
+            return None;
+        }
+        let mut time = 0.;
+        for _ in 0..MAX_TRACING_STEPS {
+            let local_point =
+                Boundary::local_point(transform, position + displacement * time, periodicity);
+            let clearance = -self.sdf.distance(local_point) - radius;
+            if clearance < 0. && time == 0. {
+                return None;
+            }
+            if clearance <= TRACING_TOLERANCE {
+                let normal = (transform.rotation * self.sdf.gradient(local_point).extend(0.)).xy();
+                return (displacement.dot(normal) > 0.).then_some((time, normal));
+            }
+            time += clearance / length;
+            if time > 1. {
+                return None;
+            }
+        }
+        None
+    }
+}
+
+/// Distance, in pixels, from the walls at which a traced particle touches
+/// them.
+const TRACING_TOLERANCE: f32 = 0.05;
+/// Steps after which a traced particle grazing along a wall is assumed to
+/// miss it.
+const MAX_TRACING_STEPS: usize = 32;
+
+pub fn enforce_bounds(
+    time: Res<Time>,
+    periodicity: Res<Periodicity>,
+    boundaries_q: Query<(&Boundary, &Transform, &KinematicVelocity), Without<FluidParticle>>,
+    mut q_particles: Query<(
+        &FluidParticle,
+        &mut Transform,
+        &Mass,
+        &Velocity,
+        &mut Forces,
+    )>,
+) {
+    for (particle, mut transform, mass, velocity, mut forces) in q_particles.iter_mut() {
+        for (boundary, boundary_transform, boundary_velocity) in boundaries_q.iter() {
+            let Some((penetration, normal)) = boundary.penetration(
+                boundary_transform,
+                transform.translation.xy(),
+                particle.radius,
+                &periodicity,
+            ) else {
+                continue;
+            };
+            let offset = transform.translation.xy() - boundary_transform.translation.xy();
+
+            let collision_force = calculate_collision_force(
+                normal,
+                boundary_velocity.at(offset),
+                particle,
+                mass,
+                velocity,
+                &time,
+            );
+
+            if collision_force != Vec2::ZERO {
+                forces.add(ForceSource::Boundary, collision_force);
+            }
+
+            transform.translation -= (penetration * normal).extend(0.);
+        }
+    }
+}
+
+/// Force bouncing a particle off a wall of outward `normal` moving at
+/// `wall_velocity`.
+fn calculate_collision_force(
+    normal: Vec2,
+    wall_velocity: Vec2,
+    particle: &FluidParticle,
+    Mass(mass): &Mass,
+    Velocity(velocity): &Velocity,
+    time: &Res<Time>,
+) -> Vec2 {
+    if time.delta().as_secs_f32() == 0. {
+        return Vec2::ZERO;
+    }
+
+    let normal_velocity = (velocity - wall_velocity).dot(normal);
+    if normal_velocity <= 0. {
+        return Vec2::ZERO;
+    }
+
+    let impulse = mass * (-2. * normal_velocity * normal);
+
+    impulse * particle.restitution_coeff / time.delta().as_secs_f32()
+}
+
+/// Side, in pixels, of the texels the boundaries are drawn with.
+const BOUNDS_TEXEL_SIZE: f32 = 2.;
+
+pub fn spawn_boundary(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    container: Res<Container>,
+) {
+    let image = Image::new_fill(
+        Extent3d {
+            width: ((MAX_X - MIN_X) / BOUNDS_TEXEL_SIZE) as u32,
+            height: ((MAX_Y - MIN_Y) / BOUNDS_TEXEL_SIZE) as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+    );
+
+    commands.spawn((
+        Boundary::new(*container),
+        BoundaryMotion::Static,
+        KinematicVelocity::default(),
+        Sprite {
+            image: images.add(image),
+            custom_size: Some(Vec2::new(MAX_X - MIN_X, MAX_Y - MIN_Y)),
+            ..default()
+        },
+        Transform::from_xyz(0., 0., -1.),
+    ));
+}
+
+pub fn select_container(container: Res<Container>, mut boundaries_q: Query<&mut Boundary>) {
+    for mut boundary in boundaries_q.iter_mut() {
+        *boundary = Boundary::new(*container);
+    }
+}
+
+/// Rasterizes the boundaries around their origin, their `Transform` then
+/// places the sprite.
+pub fn draw_bounds(
+    boundaries_q: Query<(&Boundary, &Sprite), Changed<Boundary>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    for (boundary, sprite) in boundaries_q.iter() {
+        let Some(image) = images.get_mut(&sprite.image) else {
+            continue;
+        };
+        let width = image.width() as usize;
+        let height = image.height() as usize;
+        // Image rows go from the top down.
+        for (row, pixels) in image.data.chunks_exact_mut(width * 4).enumerate() {
+            for (column, pixel) in pixels.chunks_exact_mut(4).enumerate() {
+                let center = Vec2::new(
+                    (column as f32 + 0.5 - width as f32 / 2.) * BOUNDS_TEXEL_SIZE,
+                    (height as f32 / 2. - row as f32 - 0.5) * BOUNDS_TEXEL_SIZE,
+                );
+                let alpha = if boundary.sdf.distance(center) < 0. {
+                    26
+                } else {
+                    0
+                };
+                pixel.copy_from_slice(&[255, 255, 255, alpha]);
+            }
+        }
+    }
+}
+
+pub const MIN_X: f32 = -200.;
+pub const MAX_X: f32 = 200.;
+pub const MIN_Y: f32 = -200.;
+pub const MAX_Y: f32 = 200.;
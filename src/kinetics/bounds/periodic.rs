@@ -0,0 +1,49 @@
+use bevy::prelude::*;
+
+use super::{MAX_X, MAX_Y, MIN_X, MIN_Y};
+
+/// Axes along which the domain wraps around: a particle leaving one side
+/// comes back through the opposite one, and particles near both sides are
+/// neighbours. The container walls only act along the other axes.
+/// The grids of `Solver::StableFluids` and `Solver::Flip` keep their walls.
+#[derive(Resource, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Periodicity {
+    pub x: bool,
+    pub y: bool,
+}
+
+impl Periodicity {
+    /// Shortest offset from `from` to `to`, going through the wrapped sides
+    /// when that is shorter (minimum image convention).
+    pub fn offset(&self, from: Vec2, to: Vec2) -> Vec2 {
+        let offset = to - from;
+        Vec2::new(
+            minimum_image(offset.x, MAX_X - MIN_X, self.x),
+            minimum_image(offset.y, MAX_Y - MIN_Y, self.y),
+        )
+    }
+
+    /// Brings `position` back into the domain along the periodic axes.
+    pub fn wrap(&self, position: Vec2) -> Vec2 {
+        Vec2::new(
+            wrap(position.x, MIN_X, MAX_X, self.x),
+            wrap(position.y, MIN_Y, MAX_Y, self.y),
+        )
+    }
+}
+
+fn minimum_image(offset: f32, period: f32, periodic: bool) -> f32 {
+    if periodic {
+        offset - period * (offset / period).round()
+    } else {
+        offset
+    }
+}
+
+fn wrap(value: f32, min: f32, max: f32, periodic: bool) -> f32 {
+    if periodic {
+        min + (value - min).rem_euclid(max - min)
+    } else {
+        value
+    }
+}
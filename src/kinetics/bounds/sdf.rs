@@ -0,0 +1,130 @@
+use bevy::prelude::*;
+
+use crate::kinetics::obstacles::shape::ObstacleShape;
+
+/// Step, in pixels, of the central differences used for the gradient.
+const GRADIENT_STEP: f32 = 0.5;
+
+/// Signed distance field, in pixels, negative inside.
+#[derive(Clone, Debug)]
+pub enum Sdf {
+    /// `shape` centered on `center` and turned by `rotation` radians.
+    Shape {
+        shape: ObstacleShape,
+        center: Vec2,
+        rotation: f32,
+    },
+    Union(Box<Sdf>, Box<Sdf>),
+    /// The first field with the second one carved out of it.
+    Subtraction(Box<Sdf>, Box<Sdf>),
+    Sampled(SampledSdf),
+}
+
+impl Sdf {
+    pub fn shape(shape: ObstacleShape, center: Vec2) -> Sdf {
+        Sdf::Shape {
+            shape,
+            center,
+            rotation: 0.,
+        }
+    }
+
+    pub fn rectangle(min: Vec2, max: Vec2) -> Sdf {
+        Sdf::shape(
+            ObstacleShape::Rectangle {
+                half_size: (max - min) / 2.,
+            },
+            (min + max) / 2.,
+        )
+    }
+
+    pub fn union(self, other: Sdf) -> Sdf {
+        Sdf::Union(Box::new(self), Box::new(other))
+    }
+
+    pub fn subtract(self, other: Sdf) -> Sdf {
+        Sdf::Subtraction(Box::new(self), Box::new(other))
+    }
+
+    pub fn distance(&self, point: Vec2) -> f32 {
+        match self {
+            Sdf::Shape {
+                shape,
+                center,
+                rotation,
+            } => {
+                shape
+                    .signed_distance(Vec2::from_angle(-rotation).rotate(point - *center))
+                    .0
+            }
+            Sdf::Union(first, second) => first.distance(point).min(second.distance(point)),
+            Sdf::Subtraction(first, second) => first.distance(point).max(-second.distance(point)),
+            Sdf::Sampled(sampled) => sampled.distance(point),
+        }
+    }
+
+    /// Normalized gradient of the field, pointing outwards.
+    pub fn gradient(&self, point: Vec2) -> Vec2 {
+        let along = |step: Vec2| self.distance(point + step) - self.distance(point - step);
+        Vec2::new(
+            along(Vec2::X * GRADIENT_STEP),
+            along(Vec2::Y * GRADIENT_STEP),
+        )
+        .try_normalize()
+        .unwrap_or(Vec2::ZERO)
+    }
+
+    /// Samples the field every `cell_size` pixels between `min` and `max`, so
+    /// evaluating it no longer depends on how it was built.
+    pub fn sampled(&self, min: Vec2, max: Vec2, cell_size: f32) -> Sdf {
+        let columns = ((max.x - min.x) / cell_size).ceil() as usize + 1;
+        let rows = ((max.y - min.y) / cell_size).ceil() as usize + 1;
+        let values = (0..rows)
+            .flat_map(|j| {
+                (0..columns).map(move |i| min + Vec2::new(i as f32, j as f32) * cell_size)
+            })
+            .map(|point| self.distance(point))
+            .collect();
+        Sdf::Sampled(SampledSdf {
+            min,
+            cell_size,
+            columns,
+            rows,
+            values,
+        })
+    }
+}
+
+/// Distances on a regular grid, bilinearly interpolated. Points outside of
+/// the grid get the distance of the closest point on its edge, plus how far
+/// they are from it, so that the gradient still leads back to the grid.
+#[derive(Clone, Debug)]
+pub struct SampledSdf {
+    min: Vec2,
+    cell_size: f32,
+    columns: usize,
+    rows: usize,
+    /// Row-major, from the bottom row up.
+    values: Vec<f32>,
+}
+
+impl SampledSdf {
+    fn distance(&self, point: Vec2) -> f32 {
+        let unclamped = (point - self.min) / self.cell_size;
+        let grid_position = unclamped.clamp(
+            Vec2::ZERO,
+            Vec2::new((self.columns - 1) as f32, (self.rows - 1) as f32),
+        );
+        let outside = (unclamped - grid_position).length() * self.cell_size;
+        let (i, j) = (
+            (grid_position.x as usize).min(self.columns - 2),
+            (grid_position.y as usize).min(self.rows - 2),
+        );
+        let (tx, ty) = (grid_position.x - i as f32, grid_position.y - j as f32);
+        let value = |i: usize, j: usize| self.values[i + j * self.columns];
+
+        let lower = value(i, j) * (1. - tx) + value(i + 1, j) * tx;
+        let upper = value(i, j + 1) * (1. - tx) + value(i + 1, j + 1) * tx;
+        lower * (1. - ty) + upper * ty + outside
+    }
+}
@@ -0,0 +1,76 @@
+#[cfg(test)]
+mod bounds_tests {
+
+    use super::super::*;
+
+    #[test]
+    fn box_container_pushes_back_along_the_wall_normal() {
+        let boundary = Boundary::new(Container::Box);
+        let point = Vec2::new(MAX_X - 1., 20.);
+        assert_eq!(boundary.sdf.distance(point), -1.);
+        assert_eq!(boundary.sdf.gradient(point), Vec2::X);
+    }
+
+    #[test]
+    fn subtracted_shape_is_outside() {
+        let sdf = Sdf::shape(ObstacleShape::Circle { radius: 50. }, Vec2::ZERO).subtract(
+            Sdf::shape(ObstacleShape::Circle { radius: 20. }, Vec2::ZERO),
+        );
+        assert!(sdf.distance(Vec2::ZERO) > 0.);
+        assert!(sdf.distance(Vec2::new(35., 0.)) < 0.);
+        // Inside the hole the wall is the inner circle, so the normal points
+        // towards its center.
+        assert!(sdf.gradient(Vec2::new(10., 0.)).x < 0.);
+    }
+
+    #[test]
+    fn sampled_field_matches_the_analytic_one_on_its_nodes() {
+        let sdf = Sdf::shape(ObstacleShape::Circle { radius: 50. }, Vec2::ZERO);
+        let sampled = sdf.sampled(Vec2::splat(-100.), Vec2::splat(100.), 4.);
+        for point in [
+            Vec2::new(0., 0.),
+            Vec2::new(48., -20.),
+            Vec2::new(-100., 100.),
+        ] {
+            assert!((sampled.distance(point) - sdf.distance(point)).abs() < 1e-3);
+        }
+        // Past the grid the field keeps growing away from it.
+        assert!((sampled.distance(Vec2::new(0., -130.)) - 80.).abs() < 1e-3);
+        assert_eq!(sampled.gradient(Vec2::new(0., -130.)), Vec2::NEG_Y);
+    }
+
+    #[test]
+    fn particles_are_swept_onto_the_walls_and_pushed_out_of_them() {
+        let boundary = Boundary::new(Container::Box);
+        let transform = Transform::from_xyz(0., 10., 0.);
+        let periodicity = Periodicity::default();
+        let (time, normal) = boundary
+            .time_of_impact(
+                &transform,
+                Vec2::new(0., 100.),
+                Vec2::new(0., 200.),
+                5.,
+                &periodicity,
+            )
+            .unwrap();
+        assert!((time - 0.525).abs() < 1e-3, "{time}");
+        assert_eq!(normal, Vec2::Y);
+
+        let (penetration, normal) = boundary
+            .penetration(&transform, Vec2::new(0., 212.), 5., &periodicity)
+            .unwrap();
+        assert!((penetration - 7.).abs() < 1e-4);
+        assert_eq!(normal, Vec2::Y);
+        let periodic = Periodicity { x: false, y: true };
+        assert!(boundary
+            .penetration(&transform, Vec2::new(0., 212.), 5., &periodic)
+            .is_none());
+    }
+
+    #[test]
+    fn keyframes_are_interpolated_and_loop() {
+        let motion = kinematic::BoundaryMotion::tilt();
+        assert_eq!(motion.pose_at(1.).unwrap().rotation, 0.125);
+        assert_eq!(motion.pose_at(7.).unwrap().rotation, 0.125);
+    }
+}
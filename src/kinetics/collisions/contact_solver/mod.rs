@@ -0,0 +1,340 @@
+mod tests;
+
+use bevy::{prelude::*, utils::HashMap};
+
+use super::{CollidingParticles, UnorderedEntitiesPair};
+use crate::kinetics::{
+    bounds::periodic::Periodicity,
+    forces::ForceSource,
+    mass::Mass,
+    velocity::{Velocity, PIXELS_PER_METER},
+};
+
+/// Parameters of the sequential impulse contact solver.
+#[derive(Resource, Clone, Copy)]
+pub struct ContactSolverSettings {
+    pub velocity_iterations: usize,
+    pub position_iterations: usize,
+    /// Fraction of the penetration removed per step by the split impulse.
+    pub baumgarte: f32,
+    /// Penetration, in pixels, left uncorrected so resting contacts don't jitter.
+    pub allowed_penetration: f32,
+    /// Approach speed, in m/s, below which contacts don't bounce.
+    pub restitution_threshold: f32,
+    /// Starts every step from the impulses accumulated at the previous one.
+    pub warm_starting: bool,
+    pub accumulation: ContactAccumulation,
+}
+
+/// How the impulses of the contacts touching the same particle are combined
+/// within an iteration.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ContactAccumulation {
+    /// Each contact is solved against the velocities left by the contacts
+    /// before it (Gauss-Seidel). Converges the fastest, but the result
+    /// depends on the order of the contacts.
+    #[default]
+    Sequential,
+    /// Every contact is solved against the velocities at the start of the
+    /// iteration, then the impulses are summed per particle and applied
+    /// together (Jacobi). A particle with `n` contacts counts as `n` times
+    /// lighter in each of them so that the sum doesn't overshoot.
+    Jacobi,
+}
+
+impl Default for ContactSolverSettings {
+    fn default() -> Self {
+        ContactSolverSettings {
+            velocity_iterations: 8,
+            position_iterations: 4,
+            baumgarte: 0.2,
+            allowed_penetration: 0.5,
+            restitution_threshold: 0.5,
+            warm_starting: true,
+            accumulation: ContactAccumulation::default(),
+        }
+    }
+}
+
+/// Normal and tangent impulses accumulated on every contact at the previous
+/// step, keyed by the pair of particles.
+#[derive(Resource, Default)]
+pub struct ContactCache {
+    impulses: HashMap<UnorderedEntitiesPair, (f32, f32)>,
+}
+
+struct Body {
+    entity: Entity,
+    center: Vec2,
+    radius: f32,
+    restitution_coeff: f32,
+    friction_coeff: f32,
+    inverse_mass: f32,
+    velocity: Vec2,
+    initial_velocity: Vec2,
+    /// Velocity only used to push overlapping particles apart, it is not
+    /// kept after the step so the correction doesn't add energy.
+    pseudo_velocity: Vec2,
+}
+
+struct Contact {
+    pair: UnorderedEntitiesPair,
+    bodies: (usize, usize),
+    /// From the first body towards the second one.
+    normal: Vec2,
+    /// In meters.
+    penetration: f32,
+    effective_mass: f32,
+    friction_coeff: f32,
+    /// Normal velocity the contact should separate with.
+    restitution_bias: f32,
+    normal_impulse: f32,
+    tangent_impulse: f32,
+    pseudo_impulse: f32,
+}
+
+/// Velocity of a `Body` an impulse is applied to.
+type BodyVelocity = fn(&mut Body) -> &mut Vec2;
+
+fn velocity(body: &mut Body) -> &mut Vec2 {
+    &mut body.velocity
+}
+
+fn pseudo_velocity(body: &mut Body) -> &mut Vec2 {
+    &mut body.pseudo_velocity
+}
+
+impl Contact {
+    fn apply(&self, bodies: &mut [Body], impulse: Vec2, body_velocity: BodyVelocity) {
+        let (body1, body2) = self.bodies;
+        let inverse_mass = bodies[body1].inverse_mass;
+        *body_velocity(&mut bodies[body1]) -= impulse * inverse_mass;
+        let inverse_mass = bodies[body2].inverse_mass;
+        *body_velocity(&mut bodies[body2]) += impulse * inverse_mass;
+    }
+
+    fn relative_velocity(&self, bodies: &[Body]) -> Vec2 {
+        bodies[self.bodies.1].velocity - bodies[self.bodies.0].velocity
+    }
+
+    /// Updates the accumulated friction and normal impulses from the current
+    /// velocities, returning the impulse left to apply.
+    fn solve_velocity(&mut self, bodies: &[Body]) -> Vec2 {
+        let tangent = self.normal.perp();
+        let relative_velocity = self.relative_velocity(bodies);
+        // The friction doesn't change the normal velocity, so both come from
+        // the same relative velocity.
+        let max_friction = self.friction_coeff * self.normal_impulse;
+        let tangent_impulse = (self.tangent_impulse
+            - self.effective_mass * relative_velocity.dot(tangent))
+        .clamp(-max_friction, max_friction);
+        let normal_impulse = (self.normal_impulse
+            + self.effective_mass * (self.restitution_bias - relative_velocity.dot(self.normal)))
+        .max(0.);
+        let impulse = tangent * (tangent_impulse - self.tangent_impulse)
+            + self.normal * (normal_impulse - self.normal_impulse);
+        self.tangent_impulse = tangent_impulse;
+        self.normal_impulse = normal_impulse;
+        impulse
+    }
+
+    /// Updates the accumulated split impulse pushing the particles apart by
+    /// `bias` m/s, returning the impulse left to apply.
+    fn solve_position(&mut self, bodies: &[Body], bias: f32) -> Vec2 {
+        let (body1, body2) = (&bodies[self.bodies.0], &bodies[self.bodies.1]);
+        let separation_velocity = (body2.pseudo_velocity - body1.pseudo_velocity).dot(self.normal);
+        let pseudo_impulse =
+            (self.pseudo_impulse + self.effective_mass * (bias - separation_velocity)).max(0.);
+        let impulse = self.normal * (pseudo_impulse - self.pseudo_impulse);
+        self.pseudo_impulse = pseudo_impulse;
+        impulse
+    }
+}
+
+/// Runs one iteration over the contacts, `solve` giving the impulse a
+/// contact adds to `body_velocity`.
+fn iterate(
+    contacts: &mut [Contact],
+    bodies: &mut [Body],
+    accumulation: ContactAccumulation,
+    body_velocity: BodyVelocity,
+    mut solve: impl FnMut(&mut Contact, &[Body]) -> Vec2,
+) {
+    match accumulation {
+        ContactAccumulation::Sequential => {
+            for contact in contacts.iter_mut() {
+                let impulse = solve(contact, bodies);
+                contact.apply(bodies, impulse, body_velocity);
+            }
+        }
+        ContactAccumulation::Jacobi => {
+            let impulses: Vec<Vec2> = contacts
+                .iter_mut()
+                .map(|contact| solve(contact, bodies))
+                .collect();
+            for (contact, impulse) in contacts.iter().zip(impulses) {
+                contact.apply(bodies, impulse, body_velocity);
+            }
+        }
+    }
+}
+
+/// Resolves the contacts between `pairs` of overlapping particles with
+/// sequential impulses: the velocities are changed directly, with Coulomb
+/// friction, and the overlaps are removed with split impulses. The impulses
+/// of every contact of a particle add up, and its velocity and position are
+/// written back once at the end.
+pub(super) fn solve(
+    pairs: impl IntoIterator<Item = UnorderedEntitiesPair>,
+    particles_q: &mut CollidingParticles,
+    contact_cache: &mut ContactCache,
+    settings: &ContactSolverSettings,
+    periodicity: &Periodicity,
+    delta: f32,
+) {
+    let mut bodies: Vec<Body> = vec![];
+    let mut indices: HashMap<Entity, usize> = HashMap::new();
+    let mut body_idx = |entity: Entity, bodies: &mut Vec<Body>| -> Option<usize> {
+        if let Some(idx) = indices.get(&entity) {
+            return Some(*idx);
+        }
+        let (_, particle, transform, Mass(mass), Velocity(velocity), _) =
+            particles_q.get(entity).ok()?;
+        bodies.push(Body {
+            entity,
+            center: transform.translation.xy(),
+            radius: particle.radius,
+            restitution_coeff: particle.restitution_coeff,
+            friction_coeff: particle.friction_coeff,
+            inverse_mass: if *mass > 0. { 1. / mass } else { 0. },
+            velocity: *velocity,
+            initial_velocity: *velocity,
+            pseudo_velocity: Vec2::ZERO,
+        });
+        indices.insert(entity, bodies.len() - 1);
+        Some(bodies.len() - 1)
+    };
+
+    let mut contacts: Vec<Contact> = vec![];
+    for pair in pairs {
+        let (Some(idx1), Some(idx2)) = (
+            body_idx(pair.entities.0, &mut bodies),
+            body_idx(pair.entities.1, &mut bodies),
+        ) else {
+            continue;
+        };
+        let (body1, body2) = (&bodies[idx1], &bodies[idx2]);
+        let offset = periodicity.offset(body1.center, body2.center);
+        let penetration = body1.radius + body2.radius - offset.length();
+        let inverse_masses = body1.inverse_mass + body2.inverse_mass;
+        if penetration < 0. || inverse_masses == 0. {
+            continue;
+        }
+        // Particles at the same spot are pushed apart vertically.
+        let normal = offset.try_normalize().unwrap_or(Vec2::Y);
+        let approach_velocity = (body2.velocity - body1.velocity).dot(normal);
+        let restitution_coeff = body1.restitution_coeff.max(body2.restitution_coeff);
+
+        contacts.push(Contact {
+            pair,
+            bodies: (idx1, idx2),
+            normal,
+            penetration: penetration / PIXELS_PER_METER,
+            effective_mass: 1. / inverse_masses,
+            friction_coeff: (body1.friction_coeff * body2.friction_coeff).sqrt(),
+            restitution_bias: if approach_velocity < -settings.restitution_threshold {
+                -restitution_coeff * approach_velocity
+            } else {
+                0.
+            },
+            normal_impulse: 0.,
+            tangent_impulse: 0.,
+            pseudo_impulse: 0.,
+        });
+    }
+
+    if settings.accumulation == ContactAccumulation::Jacobi {
+        let mut amounts_of_contacts = vec![0.; bodies.len()];
+        for contact in contacts.iter() {
+            amounts_of_contacts[contact.bodies.0] += 1.;
+            amounts_of_contacts[contact.bodies.1] += 1.;
+        }
+        for contact in contacts.iter_mut() {
+            let (body1, body2) = contact.bodies;
+            contact.effective_mass = 1.
+                / (amounts_of_contacts[body1] * bodies[body1].inverse_mass
+                    + amounts_of_contacts[body2] * bodies[body2].inverse_mass);
+        }
+    }
+
+    if settings.warm_starting {
+        for contact in contacts.iter_mut() {
+            if let Some((normal_impulse, tangent_impulse)) =
+                contact_cache.impulses.get(&contact.pair)
+            {
+                contact.normal_impulse = *normal_impulse;
+                contact.tangent_impulse = *tangent_impulse;
+                let tangent = contact.normal.perp();
+                contact.apply(
+                    &mut bodies,
+                    contact.normal * *normal_impulse + tangent * *tangent_impulse,
+                    velocity,
+                );
+            }
+        }
+    }
+
+    for _ in 0..settings.velocity_iterations {
+        iterate(
+            &mut contacts,
+            &mut bodies,
+            settings.accumulation,
+            velocity,
+            Contact::solve_velocity,
+        );
+    }
+
+    if delta > 0. {
+        let allowed_penetration = settings.allowed_penetration / PIXELS_PER_METER;
+        for _ in 0..settings.position_iterations {
+            iterate(
+                &mut contacts,
+                &mut bodies,
+                settings.accumulation,
+                pseudo_velocity,
+                |contact, bodies| {
+                    let bias = settings.baumgarte / delta
+                        * (contact.penetration - allowed_penetration).max(0.);
+                    contact.solve_position(bodies, bias)
+                },
+            );
+        }
+    }
+
+    contact_cache.impulses = contacts
+        .iter()
+        .map(|contact| {
+            (
+                contact.pair,
+                (contact.normal_impulse, contact.tangent_impulse),
+            )
+        })
+        .collect();
+
+    for body in bodies {
+        if let Ok((_, _, mut transform, Mass(mass), mut velocity, forces)) =
+            particles_q.get_mut(body.entity)
+        {
+            velocity.0 = body.velocity;
+            if let (Some(mut forces), true) = (forces, delta > 0.) {
+                forces.add(
+                    ForceSource::Collision,
+                    mass * (body.velocity - body.initial_velocity) / delta,
+                );
+            }
+            let center =
+                periodicity.wrap(body.center + body.pseudo_velocity * delta * PIXELS_PER_METER);
+            transform.translation = center.extend(transform.translation.z);
+        }
+    }
+}
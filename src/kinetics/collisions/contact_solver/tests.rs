@@ -0,0 +1,223 @@
+#[cfg(test)]
+mod contact_solver_tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::super::*;
+    use crate::{fluids::particle::FluidParticle, kinetics::forces::Forces};
+
+    const DELTA: f32 = 1. / 144.;
+
+    fn spawn_particle(world: &mut World, center: Vec2, mass: f32, velocity: Vec2) -> Entity {
+        world
+            .spawn((
+                FluidParticle {
+                    radius: 5.,
+                    restitution_coeff: 0.,
+                    friction_coeff: 0.,
+                },
+                Transform::from_translation(center.extend(0.)),
+                Mass(mass),
+                Velocity(velocity),
+                Forces::default(),
+            ))
+            .id()
+    }
+
+    fn solve_pairs(
+        world: &mut World,
+        pairs: Vec<(Entity, Entity)>,
+        accumulation: ContactAccumulation,
+    ) {
+        solve_pairs_with(
+            world,
+            pairs,
+            ContactSolverSettings {
+                accumulation,
+                ..default()
+            },
+        );
+    }
+
+    /// Solves `pairs` with the `ContactCache` of the world, kept from a call
+    /// to the other.
+    fn solve_pairs_with(
+        world: &mut World,
+        pairs: Vec<(Entity, Entity)>,
+        settings: ContactSolverSettings,
+    ) {
+        world.init_resource::<ContactCache>();
+        world
+            .run_system_once(
+                move |mut particles_q: CollidingParticles,
+                      mut contact_cache: ResMut<ContactCache>| {
+                    solve(
+                        pairs
+                            .iter()
+                            .map(|(e1, e2)| UnorderedEntitiesPair::new(*e1, *e2)),
+                        &mut particles_q,
+                        &mut contact_cache,
+                        &settings,
+                        &Periodicity::default(),
+                        DELTA,
+                    );
+                },
+            )
+            .unwrap();
+    }
+
+    fn center(world: &World, entity: Entity) -> Vec2 {
+        world.get::<Transform>(entity).unwrap().translation.xy()
+    }
+
+    fn velocity(world: &World, entity: Entity) -> Vec2 {
+        world.get::<Velocity>(entity).unwrap().0
+    }
+
+    #[test]
+    fn particle_touching_three_others_gets_every_correction() {
+        for accumulation in [ContactAccumulation::Sequential, ContactAccumulation::Jacobi] {
+            let mut world = World::new();
+            let middle = spawn_particle(&mut world, Vec2::ZERO, 1., Vec2::ZERO);
+            let neighbours: Vec<(Entity, Vec2)> = (0..3)
+                .map(|idx| {
+                    let direction = Vec2::from_angle(idx as f32 * 2. * std::f32::consts::PI / 3.);
+                    let entity = spawn_particle(&mut world, direction * 8., 1., -direction * 2.);
+                    (entity, direction)
+                })
+                .collect();
+            solve_pairs(
+                &mut world,
+                neighbours
+                    .iter()
+                    .map(|(neighbour, _)| (middle, *neighbour))
+                    .collect(),
+                accumulation,
+            );
+
+            // The three contacts push the middle particle evenly, so it stays
+            // in place while each neighbour is slowed down and moved away.
+            assert!(center(&world, middle).length() < 0.01, "{accumulation:?}");
+            assert!(velocity(&world, middle).length() < 0.01, "{accumulation:?}");
+            for (neighbour, direction) in neighbours {
+                assert!(
+                    center(&world, neighbour).dot(direction) > 8.,
+                    "{accumulation:?}"
+                );
+                assert!(
+                    velocity(&world, neighbour).dot(direction) > -0.5,
+                    "{accumulation:?}"
+                );
+                let collision_force =
+                    world.get::<Forces>(neighbour).unwrap()[ForceSource::Collision];
+                assert!(collision_force.dot(direction) > 0., "{accumulation:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn stacked_particles_come_to_rest_on_a_static_one() {
+        for accumulation in [ContactAccumulation::Sequential, ContactAccumulation::Jacobi] {
+            let mut world = World::new();
+            let stack: Vec<Entity> = (0..4)
+                .map(|idx| {
+                    let (mass, velocity) = if idx == 0 {
+                        (0., Vec2::ZERO)
+                    } else {
+                        (1., Vec2::new(0., -3.))
+                    };
+                    spawn_particle(&mut world, Vec2::new(0., idx as f32 * 9.), mass, velocity)
+                })
+                .collect();
+            for _ in 0..200 {
+                for entity in stack.iter().skip(1) {
+                    world.get_mut::<Velocity>(*entity).unwrap().0.y -= 9.81 * DELTA;
+                }
+                solve_pairs(
+                    &mut world,
+                    stack.windows(2).map(|pair| (pair[0], pair[1])).collect(),
+                    accumulation,
+                );
+                for entity in stack.iter() {
+                    let velocity = velocity(&world, *entity);
+                    world.get_mut::<Transform>(*entity).unwrap().translation +=
+                        (velocity * DELTA * PIXELS_PER_METER).extend(0.);
+                }
+            }
+
+            assert_eq!(center(&world, stack[0]), Vec2::ZERO);
+            for pair in stack.windows(2) {
+                let gap = center(&world, pair[1]).y - center(&world, pair[0]).y;
+                assert!((9.2..10.).contains(&gap), "{accumulation:?}: {gap}");
+                assert!(
+                    velocity(&world, pair[1]).y.abs() < 0.2,
+                    "{accumulation:?}: {}",
+                    velocity(&world, pair[1]).y
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn friction_takes_at_most_its_share_of_the_normal_impulse() {
+        // Sliding at 2 m/s while pressed at 1 m/s against a static particle,
+        // so the normal impulse is 1 N·s.
+        let tangent_velocity_after = |friction_coeff: f32| {
+            let mut world = World::new();
+            let ground = spawn_particle(&mut world, Vec2::ZERO, 0., Vec2::ZERO);
+            let slider = spawn_particle(&mut world, Vec2::new(0., 9.5), 1., Vec2::new(2., -1.));
+            for entity in [ground, slider] {
+                world
+                    .get_mut::<FluidParticle>(entity)
+                    .unwrap()
+                    .friction_coeff = friction_coeff;
+            }
+            solve_pairs(&mut world, vec![(ground, slider)], default());
+            assert!(velocity(&world, slider).y.abs() < 1e-4);
+            velocity(&world, slider).x
+        };
+        assert!((tangent_velocity_after(0.) - 2.).abs() < 1e-4);
+        assert!((tangent_velocity_after(0.5) - 1.5).abs() < 1e-4);
+        assert!(tangent_velocity_after(10.).abs() < 1e-4);
+    }
+
+    #[test]
+    fn warm_starting_brings_a_stack_to_rest_in_fewer_iterations() {
+        let remaining_speed = |warm_starting: bool| {
+            let mut world = World::new();
+            let stack: Vec<Entity> = (0..6)
+                .map(|idx| {
+                    let mass = if idx == 0 { 0. } else { 1. };
+                    spawn_particle(
+                        &mut world,
+                        Vec2::new(0., idx as f32 * 9.6),
+                        mass,
+                        Vec2::ZERO,
+                    )
+                })
+                .collect();
+            let settings = ContactSolverSettings {
+                velocity_iterations: 1,
+                position_iterations: 0,
+                warm_starting,
+                ..default()
+            };
+            for _ in 0..100 {
+                for entity in stack.iter().skip(1) {
+                    world.get_mut::<Velocity>(*entity).unwrap().0.y -= 9.81 * DELTA;
+                }
+                solve_pairs_with(
+                    &mut world,
+                    stack.windows(2).map(|pair| (pair[0], pair[1])).collect(),
+                    settings,
+                );
+            }
+            stack
+                .iter()
+                .map(|entity| velocity(&world, *entity).length())
+                .sum::<f32>()
+        };
+        let (warm, cold) = (remaining_speed(true), remaining_speed(false));
+        assert!(warm < 1e-3, "{warm}");
+        assert!(warm < cold / 10., "{warm} {cold}");
+    }
+}
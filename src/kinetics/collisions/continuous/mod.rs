@@ -0,0 +1,266 @@
+mod tests;
+
+use bevy::prelude::*;
+
+use super::{position_hashing::PositionHashMap, CollidableParticle};
+use crate::{
+    fluids::particle::FluidParticle,
+    kinetics::{
+        bounds::{kinematic::KinematicVelocity, periodic::Periodicity, Boundary, MIN_X, MIN_Y},
+        mass::Mass,
+        velocity::{displace, Velocity, PIXELS_PER_METER},
+    },
+};
+
+/// Moves every particle by its velocity over `delta` seconds like
+/// `velocity::move_entities`, but sweeps the particles along their paths
+/// first. A particle that would hit another particle or a wall during the
+/// step is stopped at the contact point, bounces there and travels the rest
+/// of the step with its new velocity. Moving walls are swept against with
+/// the particle's velocity relative to them.
+/// Each particle resolves at most its earliest impact per call, overlapping
+/// particles are left to `apply_collisions`.
+pub fn advance(world: &mut World, delta: f32) {
+    let periodicity = *world.resource::<Periodicity>();
+    let mut particles_q =
+        world.query::<(Entity, &FluidParticle, &Mass, &mut Velocity, &mut Transform)>();
+    let particles: Vec<SweptParticle> = particles_q
+        .iter(world)
+        .map(
+            |(entity, particle, mass, velocity, transform)| SweptParticle {
+                entity,
+                particle: *particle,
+                mass: *mass,
+                velocity: velocity.clone(),
+                center: transform.translation.xy(),
+                displacement: velocity.0 * delta * PIXELS_PER_METER,
+            },
+        )
+        .collect();
+
+    let mut boundaries_q = world
+        .query_filtered::<(&Boundary, &Transform, &KinematicVelocity), Without<FluidParticle>>();
+    let boundaries: Vec<(&Boundary, &Transform, &KinematicVelocity)> =
+        boundaries_q.iter(world).collect();
+    let mut impacts = find_impacts(&particles, &boundaries, delta, &periodicity);
+    impacts.sort_by(|impact1, impact2| impact1.time.total_cmp(&impact2.time));
+
+    let mut new_velocities: Vec<Option<Vec2>> = vec![None; particles.len()];
+    let mut impact_times = vec![1.; particles.len()];
+    for impact in impacts {
+        match impact.other {
+            ImpactTarget::Particle(other) => {
+                if new_velocities[impact.particle].is_some() || new_velocities[other].is_some() {
+                    continue;
+                }
+                let (particle1, particle2) = (&particles[impact.particle], &particles[other]);
+                let collidable_p1 = particle1.collidable_at(impact.time);
+                let mut collidable_p2 = particle2.collidable_at(impact.time);
+                // The other particle may be on the far side of a periodic axis.
+                collidable_p2.particle_center = collidable_p1.particle_center
+                    + periodicity
+                        .offset(collidable_p1.particle_center, collidable_p2.particle_center);
+                let (velocity1, velocity2) =
+                    collidable_p1.velocities_after_collision_with(&collidable_p2);
+                new_velocities[impact.particle] = Some(velocity1);
+                new_velocities[other] = Some(velocity2);
+                impact_times[impact.particle] = impact.time;
+                impact_times[other] = impact.time;
+            }
+            ImpactTarget::Wall {
+                normal,
+                velocity: wall_velocity,
+            } => {
+                if new_velocities[impact.particle].is_some() {
+                    continue;
+                }
+                let particle = &particles[impact.particle];
+                let relative_velocity = particle.velocity.0 - wall_velocity;
+                new_velocities[impact.particle] = Some(
+                    wall_velocity + relative_velocity
+                        - (1. + particle.particle.restitution_coeff)
+                            * relative_velocity.dot(normal)
+                            * normal,
+                );
+                impact_times[impact.particle] = impact.time;
+            }
+        }
+    }
+
+    for (idx, particle) in particles.iter().enumerate() {
+        let Ok((.., mut velocity, mut transform)) = particles_q.get_mut(world, particle.entity)
+        else {
+            continue;
+        };
+        match new_velocities[idx] {
+            Some(new_velocity) => {
+                let time = impact_times[idx];
+                transform.translation = periodicity
+                    .wrap(particle.center + particle.displacement * time)
+                    .extend(transform.translation.z);
+                velocity.0 = new_velocity;
+                displace(
+                    &mut transform,
+                    new_velocity * delta * (1. - time),
+                    &periodicity,
+                );
+            }
+            None => displace(&mut transform, particle.velocity.0 * delta, &periodicity),
+        }
+    }
+}
+
+/// Side, in pixels, of the cells the sweeps are hashed in.
+const SWEPT_CELL_SIZE: usize = 8;
+
+/// Finds the impacts of the step. Every particle is hashed over the box its
+/// sweep covers, and only the particles whose boxes share a cell are tested,
+/// so a fast particle doesn't make the others search further.
+fn find_impacts(
+    particles: &[SweptParticle],
+    boundaries: &[(&Boundary, &Transform, &KinematicVelocity)],
+    delta: f32,
+    periodicity: &Periodicity,
+) -> Vec<Impact> {
+    let indices: bevy::utils::HashMap<Entity, usize> = particles
+        .iter()
+        .enumerate()
+        .map(|(idx, particle)| (particle.entity, idx))
+        .collect();
+    let mut swept_map =
+        PositionHashMap::new(SWEPT_CELL_SIZE, Vec2::new(MIN_X, MIN_Y), *periodicity);
+    for particle in particles {
+        let (min, max) = particle.swept_area();
+        swept_map.insert_area(min, max, particle.entity);
+    }
+
+    let mut impacts = vec![];
+    for (idx, particle) in particles.iter().enumerate() {
+        if let Some((time, normal, velocity)) =
+            wall_time_of_impact(particle, boundaries, delta, periodicity)
+        {
+            impacts.push(Impact {
+                time,
+                particle: idx,
+                other: ImpactTarget::Wall { normal, velocity },
+            });
+        }
+
+        let (min, max) = particle.swept_area();
+        for other in swept_map.entities_in_area(min, max) {
+            let Some(&other_idx) = indices.get(&other) else {
+                continue;
+            };
+            if other_idx <= idx {
+                continue;
+            }
+            let other_particle = &particles[other_idx];
+            if let Some(time) = time_of_impact(
+                periodicity.offset(particle.center, other_particle.center),
+                other_particle.displacement - particle.displacement,
+                particle.particle.radius + other_particle.particle.radius,
+            ) {
+                impacts.push(Impact {
+                    time,
+                    particle: idx,
+                    other: ImpactTarget::Particle(other_idx),
+                });
+            }
+        }
+    }
+    impacts
+}
+
+/// Fraction of the step after which two circles, `radius_sum` apart when
+/// touching, first touch. Circles that already overlap or move apart never
+/// hit.
+fn time_of_impact(
+    relative_center: Vec2,
+    relative_displacement: Vec2,
+    radius_sum: f32,
+) -> Option<f32> {
+    let a = relative_displacement.length_squared();
+    let half_b = relative_center.dot(relative_displacement);
+    let c = relative_center.length_squared() - radius_sum * radius_sum;
+    if a == 0. || half_b >= 0. || c <= 0. {
+        return None;
+    }
+    let discriminant = half_b * half_b - a * c;
+    if discriminant < 0. {
+        return None;
+    }
+    let time = (-half_b - discriminant.sqrt()) / a;
+    (0. ..=1.).contains(&time).then_some(time)
+}
+
+/// Fraction of the step of `delta` seconds after which the particle touches
+/// the walls of one of the `boundaries`, together with the wall's inward
+/// normal and velocity. The walls are swept against in their own frame, with
+/// their velocity where the particle starts. Periodic axes have no walls.
+fn wall_time_of_impact(
+    particle: &SweptParticle,
+    boundaries: &[(&Boundary, &Transform, &KinematicVelocity)],
+    delta: f32,
+    periodicity: &Periodicity,
+) -> Option<(f32, Vec2, Vec2)> {
+    boundaries
+        .iter()
+        .filter_map(|(boundary, transform, boundary_velocity)| {
+            let wall_velocity = boundary_velocity.at(particle.center - transform.translation.xy());
+            boundary
+                .time_of_impact(
+                    transform,
+                    particle.center,
+                    particle.displacement - wall_velocity * delta * PIXELS_PER_METER,
+                    particle.particle.radius,
+                    periodicity,
+                )
+                .map(|(time, normal)| (time, -normal, wall_velocity))
+        })
+        .min_by(|(time1, ..), (time2, ..)| time1.total_cmp(time2))
+}
+
+struct SweptParticle {
+    entity: Entity,
+    particle: FluidParticle,
+    mass: Mass,
+    velocity: Velocity,
+    center: Vec2,
+    /// Displacement over the whole step, in pixels.
+    displacement: Vec2,
+}
+
+impl SweptParticle {
+    /// Corners of the box covering the particle over the whole step.
+    fn swept_area(&self) -> (Vec2, Vec2) {
+        let end = self.center + self.displacement;
+        (
+            self.center.min(end) - self.particle.radius,
+            self.center.max(end) + self.particle.radius,
+        )
+    }
+
+    fn collidable_at(&self, time: f32) -> CollidableParticle<'_> {
+        CollidableParticle {
+            particle_center: self.center + self.displacement * time,
+            particle: &self.particle,
+            mass: &self.mass,
+            velocity: &self.velocity,
+        }
+    }
+}
+
+struct Impact {
+    time: f32,
+    particle: usize,
+    other: ImpactTarget,
+}
+
+enum ImpactTarget {
+    Particle(usize),
+    /// Inward normal and velocity, in m/s, of the wall.
+    Wall {
+        normal: Vec2,
+        velocity: Vec2,
+    },
+}
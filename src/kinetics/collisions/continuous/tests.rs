@@ -0,0 +1,155 @@
+#[cfg(test)]
+mod continuous_collisions_tests {
+
+    use super::super::*;
+    use crate::kinetics::bounds::{Container, MAX_X};
+
+    const DELTA: f32 = 1. / 60.;
+
+    fn particle_at(center: Vec2, displacement: Vec2) -> SweptParticle {
+        SweptParticle {
+            entity: Entity::from_raw(0),
+            particle: FluidParticle {
+                radius: 3.,
+                restitution_coeff: 1.,
+                friction_coeff: 0.,
+            },
+            mass: Mass(1.),
+            velocity: Velocity(displacement),
+            center,
+            displacement,
+        }
+    }
+
+    #[test]
+    fn head_on_particles_touch_halfway_through_their_paths() {
+        let time = time_of_impact(Vec2::new(16., 0.), Vec2::new(-20., 0.), 6.).unwrap();
+        assert!((time - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn particles_moving_apart_do_not_hit() {
+        assert_eq!(
+            time_of_impact(Vec2::new(16., 0.), Vec2::new(20., 0.), 6.),
+            None
+        );
+    }
+
+    #[test]
+    fn fast_particle_hits_the_wall_it_would_tunnel_through() {
+        let boundary = Boundary::new(Container::Box);
+        let boundaries = [(
+            &boundary,
+            &Transform::IDENTITY,
+            &KinematicVelocity::default(),
+        )];
+        let particle = particle_at(Vec2::new(MAX_X - 13., 0.), Vec2::new(40., 0.));
+        let (time, normal, _) =
+            wall_time_of_impact(&particle, &boundaries, DELTA, &Periodicity::default()).unwrap();
+        assert!((time - 0.25).abs() < 1e-3, "{time}");
+        assert!(normal.abs_diff_eq(Vec2::NEG_X, 1e-3), "{normal}");
+
+        let periodic = Periodicity { x: true, y: false };
+        assert!(wall_time_of_impact(&particle, &boundaries, DELTA, &periodic).is_none());
+    }
+
+    #[test]
+    fn fast_particle_hits_the_curved_wall_of_the_container() {
+        let boundary = Boundary::new(Container::Bowl);
+        let boundaries = [(
+            &boundary,
+            &Transform::IDENTITY,
+            &KinematicVelocity::default(),
+        )];
+        // The bottom of the bowl is at y = -165.
+        let particle = particle_at(Vec2::new(0., -100.), Vec2::new(0., -100.));
+        let (time, normal, _) =
+            wall_time_of_impact(&particle, &boundaries, DELTA, &Periodicity::default()).unwrap();
+        assert!((time - 0.62).abs() < 0.02, "{time}");
+        assert!(normal.abs_diff_eq(Vec2::Y, 0.05), "{normal}");
+        // Moving away from the wall it is already touching.
+        let leaving = particle_at(Vec2::new(0., -161.99), Vec2::new(0., 50.));
+        assert!(
+            wall_time_of_impact(&leaving, &boundaries, DELTA, &Periodicity::default()).is_none()
+        );
+    }
+
+    #[test]
+    fn sweeps_sharing_cells_are_tested_across_periodic_sides() {
+        let particles = [
+            particle_at(Vec2::new(MAX_X - 5., 0.), Vec2::new(20., 0.)),
+            SweptParticle {
+                entity: Entity::from_raw(1),
+                ..particle_at(Vec2::new(MIN_X + 5., 0.), Vec2::ZERO)
+            },
+            SweptParticle {
+                entity: Entity::from_raw(2),
+                ..particle_at(Vec2::new(0., 0.), Vec2::new(100., 0.))
+            },
+            SweptParticle {
+                entity: Entity::from_raw(3),
+                ..particle_at(Vec2::new(60., 0.), Vec2::ZERO)
+            },
+        ];
+        let periodicity = Periodicity { x: true, y: false };
+        let mut impacts: Vec<(usize, usize, f32)> =
+            find_impacts(&particles, &[], DELTA, &periodicity)
+                .into_iter()
+                .filter_map(|impact| match impact.other {
+                    ImpactTarget::Particle(other) => Some((impact.particle, other, impact.time)),
+                    ImpactTarget::Wall { .. } => None,
+                })
+                .collect();
+        impacts.sort_by_key(|(particle, ..)| *particle);
+        assert_eq!(impacts.len(), 2);
+        assert_eq!(impacts[0].0..impacts[0].1, 0..1);
+        assert!((impacts[0].2 - 0.2).abs() < 1e-5);
+        assert_eq!(impacts[1].0..impacts[1].1, 2..3);
+        assert!((impacts[1].2 - 0.54).abs() < 1e-5);
+    }
+
+    #[test]
+    fn moving_wall_pushes_the_particle_it_sweeps_into() {
+        let mut world = World::new();
+        world.insert_resource(Periodicity::default());
+        let wall_velocity = Vec2::new(-40., 0.);
+        let boundary = world
+            .spawn((
+                Boundary::new(Container::Box),
+                Transform::IDENTITY,
+                KinematicVelocity {
+                    linear: wall_velocity,
+                    angular: 0.,
+                },
+            ))
+            .id();
+        // The wall covers 10 of its 26.7 pixels before reaching the particle.
+        let particle = world
+            .spawn((
+                FluidParticle {
+                    radius: 3.,
+                    restitution_coeff: 1.,
+                    friction_coeff: 0.,
+                },
+                Mass(1.),
+                Velocity(Vec2::ZERO),
+                Transform::from_xyz(MAX_X - 13., 0., 0.),
+            ))
+            .id();
+
+        advance(&mut world, DELTA);
+        // The wall gets where it was going by the end of the step.
+        world.get_mut::<Transform>(boundary).unwrap().translation +=
+            (wall_velocity * DELTA * PIXELS_PER_METER).extend(0.);
+
+        let velocity = world.get::<Velocity>(particle).unwrap().0;
+        assert!(velocity.x <= wall_velocity.x, "{velocity}");
+        let position = world.get::<Transform>(particle).unwrap().translation.xy();
+        let boundary_transform = world.get::<Transform>(boundary).unwrap();
+        assert!(world
+            .get::<Boundary>(boundary)
+            .unwrap()
+            .penetration(boundary_transform, position, 3., &Periodicity::default())
+            .is_none());
+    }
+}
@@ -1,5 +1,8 @@
+pub mod query;
 mod tests;
 
+use std::ops::Range;
+
 use bevy::{
     prelude::*,
     utils::{HashMap, HashSet},
@@ -7,7 +10,10 @@ use bevy::{
 
 use crate::{
     fluids::particle::FluidParticle,
-    kinetics::bounds::{MAX_X, MAX_Y, MIN_X, MIN_Y},
+    kinetics::{
+        bounds::{periodic::Periodicity, MAX_X, MAX_Y, MIN_X, MIN_Y},
+        substeps::KineticsStep,
+    },
 };
 
 pub struct PositionHashingPlugin;
@@ -15,24 +21,27 @@ pub struct PositionHashingPlugin;
 impl Plugin for PositionHashingPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, init_maps)
-            .add_systems(FixedUpdate, update_position_map);
+            .add_observer(forget_removed_particle)
+            .add_systems(KineticsStep, update_position_map.in_set(PositionHashingSet));
     }
 }
 
-fn init_maps(mut commands: Commands) {
+/// Systems that bring the `PositionHashMap` up to date with the particles.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PositionHashingSet;
+
+fn init_maps(mut commands: Commands, periodicity: Res<Periodicity>) {
     commands.insert_resource(EntityPreviousPositionMap {
         map: HashMap::new(),
     });
     commands.insert_resource(PositionHashMap::new(
         6,
-        MIN_X as f32,
-        MAX_X as f32,
-        MIN_Y as f32,
-        MAX_Y as f32,
+        Vec2::new(MIN_X, MIN_Y),
+        *periodicity,
     ));
 }
 
-fn update_position_map(
+pub fn update_position_map(
     mut positions_map: ResMut<PositionHashMap>,
     mut entity_previous_position_map: ResMut<EntityPreviousPositionMap>,
     particles_q: Query<(Entity, &Transform, &FluidParticle)>,
@@ -54,129 +63,251 @@ fn update_position_map(
         });
 }
 
+/// Takes a particle out of the maps as soon as it is despawned or loses its
+/// `FluidParticle`, while its radius is still known.
+fn forget_removed_particle(
+    trigger: Trigger<OnRemove, FluidParticle>,
+    particles_q: Query<&FluidParticle>,
+    positions_map: Option<ResMut<PositionHashMap>>,
+    entity_previous_position_map: Option<ResMut<EntityPreviousPositionMap>>,
+) {
+    let (Some(mut positions_map), Some(mut entity_previous_position_map)) =
+        (positions_map, entity_previous_position_map)
+    else {
+        return;
+    };
+    let entity = trigger.entity();
+    let Some(prev_position) = entity_previous_position_map.map.remove(&entity) else {
+        return;
+    };
+    if let Ok(particle) = particles_q.get(entity) {
+        positions_map.remove(prev_position, particle.radius, entity);
+    }
+}
+
 #[derive(Resource)]
-struct EntityPreviousPositionMap {
+pub struct EntityPreviousPositionMap {
     map: HashMap<Entity, Vec2>,
 }
 
+/// Sparse grid of square cells, each holding the entities that intersect it.
+/// Only the cells holding entities are stored, so it is not tied to the
+/// bounds of the domain and costs the same whatever their size.
 #[derive(Resource)]
 pub struct PositionHashMap {
-    pub map: Vec<Vec<HashSet<Entity>>>,
+    pub map: HashMap<IVec2, HashSet<Entity>>,
     cell_side_size: usize,
-    min_x: f32,
-    max_x: f32,
-    min_y: f32,
-    max_y: f32,
+    /// Corner of the cell `(0, 0)`.
+    origin: Vec2,
+    /// Along periodic axes the cells on the far edge of the domain are
+    /// neighbours.
+    periodicity: Periodicity,
+    /// Amount of cells covering the domain, after which the cells wrap around
+    /// along the periodic axes.
+    amount_of_periodic_cells: IVec2,
 }
 
 impl PositionHashMap {
-    fn new(
-        cell_side_size: usize,
-        min_x: f32,
-        max_x: f32,
-        min_y: f32,
-        max_y: f32,
-    ) -> PositionHashMap {
-        let amount_of_x_cells = (max_x - min_x) as usize / cell_side_size as usize;
-        let amount_of_y_cells = (max_y - min_y) as usize / cell_side_size as usize;
+    pub fn new(cell_side_size: usize, origin: Vec2, periodicity: Periodicity) -> PositionHashMap {
+        // The last cells stick out of the domain when its size is not a
+        // multiple of the cell size, so that every wrapped position has a
+        // cell.
+        let amount_of_periodic_cells = ((Vec2::new(MAX_X - MIN_X, MAX_Y - MIN_Y))
+            / cell_side_size as f32)
+            .ceil()
+            .as_ivec2();
         PositionHashMap {
-            map: vec![vec![HashSet::new(); amount_of_y_cells]; amount_of_x_cells],
-            cell_side_size: cell_side_size,
-            min_x: min_x,
-            max_x: max_x,
-            min_y: min_y,
-            max_y: max_y,
+            map: HashMap::new(),
+            cell_side_size,
+            origin,
+            periodicity,
+            amount_of_periodic_cells,
         }
     }
 
     fn update(&mut self, prev_position: Vec2, curr_position: Vec2, radius: f32, entity: Entity) {
-        self.cells_idxs_of(prev_position, radius)
-            .iter()
-            .for_each(|(prev_cell_x, prev_cell_y)| {
-                self.map[*prev_cell_x][*prev_cell_y].remove(&entity);
-            });
-
-        self.cells_idxs_of(curr_position, radius)
+        let prev_cells = self.cells_idxs_of(prev_position, radius);
+        let curr_cells = self.cells_idxs_of(curr_position, radius);
+        if prev_cells == curr_cells {
+            return;
+        }
+        prev_cells
             .iter()
-            .for_each(|(curr_cell_x, curr_cell_y)| {
-                self.map[*curr_cell_x][*curr_cell_y].insert(entity);
-            });
+            .for_each(|prev_cell| self.remove_from_cell(*prev_cell, entity));
+        curr_cells.iter().for_each(|curr_cell| {
+            self.map.entry(*curr_cell).or_default().insert(entity);
+        });
     }
     fn insert(&mut self, position: Vec2, radius: f32, entity: Entity) {
         self.cells_idxs_of(position, radius)
             .iter()
-            .for_each(|(prev_cell_x, prev_cell_y)| {
-                self.map[*prev_cell_x][*prev_cell_y].insert(entity);
+            .for_each(|cell| {
+                self.map.entry(*cell).or_default().insert(entity);
             });
     }
 
-    fn cell_idxs_of(&self, position: Vec2) -> (usize, usize) {
-        (
-            ((position.x - self.min_x) as usize) / self.cell_side_size,
-            ((position.y - self.min_y) as usize) / self.cell_side_size,
-        )
+    fn remove(&mut self, position: Vec2, radius: f32, entity: Entity) {
+        self.cells_idxs_of(position, radius)
+            .iter()
+            .for_each(|cell| self.remove_from_cell(*cell, entity));
     }
-    fn cells_idxs_of(&self, position: Vec2, radius: f32) -> Vec<(usize, usize)> {
-        let cell_of_center = (
-            ((position.x - self.min_x) as usize) / self.cell_side_size,
-            ((position.y - self.min_y) as usize) / self.cell_side_size,
-        );
-        let mut result: Vec<(usize, usize)> = vec![cell_of_center];
-
-        let cell_border = self.cell_idxs_to_borders(cell_of_center.0, cell_of_center.1);
 
-        let intersects_left = position.x - radius <= cell_border.left;
-        let intersects_upper = position.y + radius >= cell_border.up;
-        let intersects_right = position.x + radius >= cell_border.right;
-        let intersects_lower = position.y - radius <= cell_border.down;
-
-        if intersects_left {
-            result.push((cell_of_center.0 - 1, cell_of_center.1));
-            if intersects_upper {
-                result.push((cell_of_center.0 - 1, cell_of_center.1 + 1));
+    /// Removes `entity` from `cell`, dropping the cell once it is empty.
+    fn remove_from_cell(&mut self, cell: IVec2, entity: Entity) {
+        if let Some(cell_set) = self.map.get_mut(&cell) {
+            cell_set.remove(&entity);
+            if cell_set.is_empty() {
+                self.map.remove(&cell);
             }
         }
-        if intersects_upper {
-            result.push((cell_of_center.0, cell_of_center.1 + 1));
-            if intersects_right {
-                result.push((cell_of_center.0 + 1, cell_of_center.1 + 1));
-            }
+    }
+
+    /// Registers `entity` in every cell intersecting the rectangle between
+    /// `min` and `max`, for static geometry bigger than a cell.
+    pub fn insert_area(&mut self, min: Vec2, max: Vec2, entity: Entity) {
+        for cell in self.cells_between(min, max) {
+            self.map.entry(cell).or_default().insert(entity);
         }
-        if intersects_right {
-            result.push((cell_of_center.0 + 1, cell_of_center.1));
-            if intersects_lower {
-                result.push((cell_of_center.0 + 1, cell_of_center.1 - 1));
-            }
+    }
+
+    /// Returns every entity registered in a cell that intersects the square
+    /// of side `2 * radius` around `position`. Callers are expected to filter
+    /// the candidates by the actual distance.
+    pub fn entities_near(&self, position: Vec2, radius: f32) -> Vec<Entity> {
+        self.entities_in_area(position - radius, position + radius)
+    }
+
+    /// Returns every entity registered in a cell that intersects the
+    /// rectangle between `min` and `max`, once each and sorted. The cells
+    /// iterate in an order that changes from a run to the other, so sums over
+    /// the result would not be reproducible otherwise.
+    pub fn entities_in_area(&self, min: Vec2, max: Vec2) -> Vec<Entity> {
+        if min.is_nan() || max.is_nan() {
+            return vec![];
         }
-        if intersects_lower {
-            result.push((cell_of_center.0, cell_of_center.1 - 1));
-            if intersects_left {
-                result.push((cell_of_center.0 - 1, cell_of_center.1 - 1));
+        let (first, last) = (self.cell_idxs_of(min), self.cell_idxs_of(max));
+        let columns = axis_cells_between(
+            first.x,
+            last.x,
+            self.periodicity.x,
+            self.amount_of_periodic_cells.x,
+        );
+        let rows = axis_cells_between(
+            first.y,
+            last.y,
+            self.periodicity.y,
+            self.amount_of_periodic_cells.y,
+        );
+        let mut result = Vec::new();
+        // Areas spanning more cells than there are stored ones go through
+        // the stored cells instead.
+        if columns.len() as u64 * rows.len() as u64 > self.map.len() as u64 {
+            let contains = |range: &Range<i32>, cell: i32, periodic: bool, amount_of_cells: i32| {
+                if periodic {
+                    (cell - range.start).rem_euclid(amount_of_cells) < range.len() as i32
+                } else {
+                    range.contains(&cell)
+                }
+            };
+            for (cell, cell_set) in self.map.iter() {
+                if contains(
+                    &columns,
+                    cell.x,
+                    self.periodicity.x,
+                    self.amount_of_periodic_cells.x,
+                ) && contains(
+                    &rows,
+                    cell.y,
+                    self.periodicity.y,
+                    self.amount_of_periodic_cells.y,
+                ) {
+                    result.extend(cell_set.iter().copied());
+                }
+            }
+        } else {
+            for cell in self.cells_between(min, max) {
+                if let Some(cell_set) = self.map.get(&cell) {
+                    result.extend(cell_set.iter().copied());
+                }
             }
         }
-        let amount_of_x_cells = (self.max_x - self.min_x) as usize / self.cell_side_size as usize;
-        let amount_of_y_cells = (self.max_y - self.min_y) as usize / self.cell_side_size as usize;
+        result.sort_unstable();
+        result.dedup();
         result
-            .iter()
-            .filter(|(x, y)| *x <= amount_of_x_cells - 1 && *y <= amount_of_y_cells - 1)
-            .map(|x| *x)
+    }
+
+    /// Cells covering the rectangle between `min` and `max`, wrapped around
+    /// along the periodic axes.
+    fn cells_between(&self, min: Vec2, max: Vec2) -> Vec<IVec2> {
+        let (first, last) = (self.cell_idxs_of(min), self.cell_idxs_of(max));
+        let columns = axis_cells_between(
+            first.x,
+            last.x,
+            self.periodicity.x,
+            self.amount_of_periodic_cells.x,
+        );
+        let rows = axis_cells_between(
+            first.y,
+            last.y,
+            self.periodicity.y,
+            self.amount_of_periodic_cells.y,
+        );
+        columns
+            .flat_map(|column| rows.clone().map(move |row| IVec2::new(column, row)))
+            .map(|cell| self.wrapped(cell))
             .collect()
     }
 
-    fn cell_idxs_to_borders(&self, cell_x: usize, cell_y: usize) -> Borders {
-        Borders {
-            left: (cell_x * self.cell_side_size) as f32 + self.min_x,
-            up: ((cell_y + 1) * self.cell_side_size) as f32 + self.min_y,
-            right: ((cell_x + 1) * self.cell_side_size) as f32 + self.min_x,
-            down: (cell_y * self.cell_side_size) as f32 + self.min_y,
-        }
+    /// Cell of `position`, clamped to `MAX_CELL_IDX` so that infinite or huge
+    /// areas can still be counted in cells.
+    fn cell_idxs_of(&self, position: Vec2) -> IVec2 {
+        ((position - self.origin) / self.cell_side_size as f32)
+            .floor()
+            .as_ivec2()
+            .clamp(IVec2::splat(-MAX_CELL_IDX), IVec2::splat(MAX_CELL_IDX))
+    }
+
+    /// Brings `cell` back among the cells covering the domain along the
+    /// periodic axes.
+    fn wrapped(&self, cell: IVec2) -> IVec2 {
+        let wrapped = cell.rem_euclid(self.amount_of_periodic_cells);
+        IVec2::new(
+            if self.periodicity.x {
+                wrapped.x
+            } else {
+                cell.x
+            },
+            if self.periodicity.y {
+                wrapped.y
+            } else {
+                cell.y
+            },
+        )
+    }
+
+    /// Cells intersecting the particle of `radius` at `position`, however
+    /// many cells it spans.
+    fn cells_idxs_of(&self, position: Vec2, radius: f32) -> Vec<IVec2> {
+        self.cells_between(position - radius, position + radius)
     }
 }
 
-#[derive(Debug)]
-struct Borders {
-    left: f32,
-    up: f32,
-    right: f32,
-    down: f32,
+/// Largest cell index along an axis, far beyond any simulated position but
+/// small enough for the amount of cells between two indices to fit an `i32`.
+const MAX_CELL_IDX: i32 = 1 << 29;
+
+/// Cells from `first` to `last` along an axis, going at most once around it
+/// if it is periodic.
+fn axis_cells_between(
+    first: i32,
+    last: i32,
+    periodic: bool,
+    amount_of_periodic_cells: i32,
+) -> Range<i32> {
+    let amount = last - first + 1;
+    if periodic {
+        first..first + amount.min(amount_of_periodic_cells)
+    } else {
+        first..first + amount
+    }
 }
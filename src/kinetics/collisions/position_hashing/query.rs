@@ -0,0 +1,205 @@
+use bevy::{ecs::system::SystemParam, prelude::*, utils::HashMap};
+
+use super::PositionHashMap;
+use crate::{
+    fluids::particle::FluidParticle,
+    kinetics::bounds::{periodic::Periodicity, MAX_X, MAX_Y, MIN_X, MIN_Y},
+};
+
+/// Spatial queries over the particles, for the logic built on top of the
+/// simulation. The candidates come from the `PositionHashMap`, as of the
+/// start of the last substep, then are checked against the actual positions
+/// of the particles. Distances are in pixels and go through the periodic
+/// sides.
+#[derive(SystemParam)]
+pub struct FluidQuery<'w, 's> {
+    position_hash_map: Res<'w, PositionHashMap>,
+    periodicity: Res<'w, Periodicity>,
+    particles_q: Query<'w, 's, (&'static Transform, &'static FluidParticle)>,
+}
+
+impl FluidQuery<'_, '_> {
+    /// Particles whose center is at most `radius` away from `point`, from the
+    /// closest to the farthest, with their distance to `point`.
+    pub fn within_radius(&self, point: Vec2, radius: f32) -> Vec<(Entity, f32)> {
+        let mut result: Vec<(Entity, f32)> = self
+            .position_hash_map
+            .entities_near(point, radius)
+            .into_iter()
+            .filter_map(|entity| {
+                let (transform, _) = self.particles_q.get(entity).ok()?;
+                let distance = self
+                    .periodicity
+                    .offset(point, transform.translation.xy())
+                    .length();
+                (distance <= radius).then_some((entity, distance))
+            })
+            .collect();
+        sort_by_distance(&mut result);
+        result
+    }
+
+    /// Particles whose center is inside `rect`, from the closest to the
+    /// farthest from its center, with their distance to it.
+    pub fn in_rect(&self, rect: Rect) -> Vec<(Entity, f32)> {
+        let center = rect.center();
+        let half_size = rect.half_size();
+        let mut result: Vec<(Entity, f32)> = self
+            .position_hash_map
+            .entities_in_area(rect.min, rect.max)
+            .into_iter()
+            .filter_map(|entity| {
+                let (transform, _) = self.particles_q.get(entity).ok()?;
+                let offset = self.periodicity.offset(center, transform.translation.xy());
+                offset
+                    .abs()
+                    .cmple(half_size)
+                    .all()
+                    .then_some((entity, offset.length()))
+            })
+            .collect();
+        sort_by_distance(&mut result);
+        result
+    }
+
+    /// The `k` particles whose center is the closest to `point`, from the
+    /// closest to the farthest, with their distance to `point`. The search
+    /// area doubles until it holds `k` of them or every particle.
+    pub fn nearest_k(&self, point: Vec2, k: usize) -> Vec<(Entity, f32)> {
+        if k == 0 {
+            return vec![];
+        }
+        let reach = self.reach(point);
+        let mut radius = self.position_hash_map.cell_side_size as f32;
+        loop {
+            let radius_covers_everything = radius >= reach;
+            let mut result = self.within_radius(point, radius.min(reach));
+            if result.len() >= k || radius_covers_everything {
+                result.truncate(k);
+                return result;
+            }
+            radius *= 2.;
+        }
+    }
+
+    /// Particles crossed by `ray` within `max_distance` of its origin, from
+    /// the closest to the farthest, with the distance along the ray at which
+    /// it enters them. It is zero for the particles around the origin.
+    ///
+    /// The cells are walked along the ray, so the cost grows with
+    /// `max_distance`. Along periodic axes the ray goes around the domain,
+    /// and stops after `MAX_RAYCAST_WRAPS` times its diagonal.
+    pub fn raycast(&self, ray: Ray2d, max_distance: f32) -> Vec<(Entity, f32)> {
+        let map = &*self.position_hash_map;
+        let max_distance = if self.periodicity.x || self.periodicity.y {
+            let diagonal = Vec2::new(MAX_X - MIN_X, MAX_Y - MIN_Y).length();
+            max_distance.min(MAX_RAYCAST_WRAPS * diagonal)
+        } else {
+            max_distance.min(self.reach(ray.origin))
+        };
+        let cell_side_size = map.cell_side_size as f32;
+        let direction = *ray.direction;
+
+        // Walks the cells crossed by the ray, stepping to the next cell along
+        // the axis whose border is the closest.
+        let mut cell = map.cell_idxs_of(ray.origin);
+        let step =
+            Vec2::select(direction.cmpeq(Vec2::ZERO), Vec2::ZERO, direction.signum()).as_ivec2();
+        let next_border = map.origin + (cell + step.max(IVec2::ZERO)).as_vec2() * cell_side_size;
+        let mut distance_to_border = Vec2::select(
+            step.cmpeq(IVec2::ZERO),
+            Vec2::INFINITY,
+            (next_border - ray.origin) / direction,
+        );
+        let distance_between_borders = Vec2::select(
+            step.cmpeq(IVec2::ZERO),
+            Vec2::INFINITY,
+            cell_side_size / direction.abs(),
+        );
+
+        let mut hits: HashMap<Entity, f32> = HashMap::new();
+        let mut distance_to_cell = 0.;
+        while distance_to_cell <= max_distance {
+            if let Some(cell_set) = map.map.get(&map.wrapped(cell)) {
+                let cell_center = map.origin + (cell.as_vec2() + 0.5) * cell_side_size;
+                for entity in cell_set {
+                    let Ok((transform, particle)) = self.particles_q.get(*entity) else {
+                        continue;
+                    };
+                    // The image of the particle overlapping this cell, which
+                    // is not the wrapped one once the ray went around.
+                    let center = cell_center
+                        + self
+                            .periodicity
+                            .offset(cell_center, transform.translation.xy());
+                    let Some(distance) =
+                        ray_enters_circle(ray.origin, direction, center, particle.radius)
+                    else {
+                        continue;
+                    };
+                    if distance <= max_distance {
+                        let hit = hits.entry(*entity).or_insert(distance);
+                        *hit = hit.min(distance);
+                    }
+                }
+            }
+            if distance_to_border.x < distance_to_border.y {
+                distance_to_cell = distance_to_border.x;
+                distance_to_border.x += distance_between_borders.x;
+                cell.x += step.x;
+            } else {
+                distance_to_cell = distance_to_border.y;
+                distance_to_border.y += distance_between_borders.y;
+                cell.y += step.y;
+            }
+        }
+        let mut result: Vec<(Entity, f32)> = hits.into_iter().collect();
+        sort_by_distance(&mut result);
+        result
+    }
+
+    /// Distance from `point` to the farthest corner of the cells holding
+    /// entities, beyond which there is nothing to find.
+    fn reach(&self, point: Vec2) -> f32 {
+        let map = &*self.position_hash_map;
+        let cell_side_size = map.cell_side_size as f32;
+        map.map
+            .keys()
+            .map(|cell| {
+                let min = map.origin + cell.as_vec2() * cell_side_size;
+                let max = min + cell_side_size;
+                let farthest =
+                    Vec2::select((point - min).abs().cmpgt((point - max).abs()), min, max);
+                self.periodicity.offset(point, farthest).length()
+            })
+            .fold(0., f32::max)
+    }
+}
+
+/// Lengths of the domain diagonal after which a ray going around the
+/// periodic sides stops.
+const MAX_RAYCAST_WRAPS: f32 = 4.;
+
+/// Distance along the ray of `direction` from `origin` at which it enters the
+/// circle, zero if `origin` is inside it.
+fn ray_enters_circle(origin: Vec2, direction: Vec2, center: Vec2, radius: f32) -> Option<f32> {
+    let offset = origin - center;
+    let c = offset.length_squared() - radius * radius;
+    if c <= 0. {
+        return Some(0.);
+    }
+    let b = offset.dot(direction);
+    if b > 0. {
+        return None;
+    }
+    let discriminant = b * b - c;
+    (discriminant >= 0.).then(|| -b - discriminant.sqrt())
+}
+
+/// Sorts by distance, then by entity so that equally far particles always
+/// come in the same order.
+fn sort_by_distance(result: &mut [(Entity, f32)]) {
+    result.sort_by(|(entity1, distance1), (entity2, distance2)| {
+        distance1.total_cmp(distance2).then(entity1.cmp(entity2))
+    });
+}
@@ -1,32 +1,316 @@
 #[cfg(test)]
-mod PositionHashMapTests {
+mod position_hash_map_tests {
+
+    use bevy::ecs::system::RunSystemOnce;
 
     use super::super::*;
     const CELL_SIZE: usize = 10;
     const MIN_X: f32 = -200.;
-    const MAX_X: f32 = 200.;
     const MIN_Y: f32 = -200.;
-    const MAX_Y: f32 = 200.;
     #[test]
     fn particle_is_only_in_the_cell_of_its_center() {
-        let mut map = PositionHashMap::new(CELL_SIZE, MIN_X, MAX_X, MIN_Y, MAX_Y);
+        let mut map =
+            PositionHashMap::new(CELL_SIZE, Vec2::new(MIN_X, MIN_Y), Periodicity::default());
         let center = Vec2::new(5., 5.);
         let radius = 4.;
         map.insert(center, radius, Entity::from_raw(0));
         assert_eq!(map.cells_idxs_of(center, radius).len(), 1);
-        assert_eq!(map.cells_idxs_of(center, radius)[0], (20, 20));
+        assert_eq!(map.cells_idxs_of(center, radius)[0], IVec2::new(20, 20));
     }
 
     #[test]
     fn particle_intersects_left_and_upper_and_left_upper_cells() {
-        let mut map = PositionHashMap::new(CELL_SIZE, MIN_X, MAX_X, MIN_Y, MAX_Y);
+        let mut map =
+            PositionHashMap::new(CELL_SIZE, Vec2::new(MIN_X, MIN_Y), Periodicity::default());
         let center = Vec2::new(2., 9.);
         let radius = 4.;
         map.insert(center, radius, Entity::from_raw(0));
         assert_eq!(map.cells_idxs_of(center, radius).len(), 4);
-        assert!(map.cells_idxs_of(center, radius).contains(&(20, 20)));
-        assert!(map.cells_idxs_of(center, radius).contains(&(20, 21)));
-        assert!(map.cells_idxs_of(center, radius).contains(&(19, 20)));
-        assert!(map.cells_idxs_of(center, radius).contains(&(19, 21)));
+        assert!(map
+            .cells_idxs_of(center, radius)
+            .contains(&IVec2::new(20, 20)));
+        assert!(map
+            .cells_idxs_of(center, radius)
+            .contains(&IVec2::new(20, 21)));
+        assert!(map
+            .cells_idxs_of(center, radius)
+            .contains(&IVec2::new(19, 20)));
+        assert!(map
+            .cells_idxs_of(center, radius)
+            .contains(&IVec2::new(19, 21)));
+    }
+
+    #[test]
+    fn cells_on_the_far_edge_are_neighbours_along_periodic_axes() {
+        let map = PositionHashMap::new(
+            CELL_SIZE,
+            Vec2::new(MIN_X, MIN_Y),
+            Periodicity { x: true, y: false },
+        );
+        let center = Vec2::new(MIN_X + 2., MIN_Y + 2.);
+        let cells = map.cells_idxs_of(center, 4.);
+        assert!(cells.contains(&IVec2::new(39, 0)));
+        assert!(cells.contains(&IVec2::new(39, -1)));
+        assert!(!cells.iter().any(|cell| cell.y == 39));
+        assert_eq!(cells.len(), 4);
+    }
+
+    fn world_with_maps() -> World {
+        let mut world = World::new();
+        world.insert_resource(PositionHashMap::new(
+            CELL_SIZE,
+            Vec2::new(MIN_X, MIN_Y),
+            Periodicity::default(),
+        ));
+        world.insert_resource(EntityPreviousPositionMap {
+            map: HashMap::new(),
+        });
+        world.add_observer(forget_removed_particle);
+        world
+    }
+
+    fn spawn_particle(world: &mut World, position: Vec2) -> Entity {
+        world
+            .spawn((
+                FluidParticle {
+                    radius: 4.,
+                    restitution_coeff: 1.,
+                    friction_coeff: 0.,
+                },
+                Transform::from_translation(position.extend(0.)),
+            ))
+            .id()
+    }
+
+    fn is_hashed(world: &World, entity: Entity) -> bool {
+        world
+            .resource::<PositionHashMap>()
+            .map
+            .values()
+            .any(|cell_set| cell_set.contains(&entity))
+    }
+
+    #[test]
+    fn despawned_particle_leaves_every_cell_it_occupied() {
+        let mut world = world_with_maps();
+        let despawned = spawn_particle(&mut world, Vec2::new(2., 9.));
+        let kept = spawn_particle(&mut world, Vec2::new(5., 5.));
+        world.run_system_once(update_position_map).unwrap();
+        assert!(is_hashed(&world, despawned));
+
+        world.despawn(despawned);
+
+        assert!(!is_hashed(&world, despawned));
+        assert!(is_hashed(&world, kept));
+        let previous_positions = &world.resource::<EntityPreviousPositionMap>().map;
+        assert!(!previous_positions.contains_key(&despawned));
+        assert!(previous_positions.contains_key(&kept));
+    }
+
+    #[test]
+    fn particle_moved_before_being_despawned_leaves_its_last_cells() {
+        let mut world = world_with_maps();
+        let entity = spawn_particle(&mut world, Vec2::new(-150., -150.));
+        world.run_system_once(update_position_map).unwrap();
+        world.get_mut::<Transform>(entity).unwrap().translation = Vec3::new(150., 150., 0.);
+        world.run_system_once(update_position_map).unwrap();
+
+        world.entity_mut(entity).remove::<FluidParticle>();
+
+        assert!(!is_hashed(&world, entity));
+        assert!(world.resource::<EntityPreviousPositionMap>().map.is_empty());
+    }
+
+    #[test]
+    fn particles_far_outside_the_domain_are_hashed_in_their_own_cells() {
+        let mut map =
+            PositionHashMap::new(CELL_SIZE, Vec2::new(MIN_X, MIN_Y), Periodicity::default());
+        let center = Vec2::new(-10_005., 50_005.);
+        map.insert(center, 4., Entity::from_raw(0));
+        map.insert(Vec2::new(5., 5.), 4., Entity::from_raw(1));
+        assert_eq!(map.map.len(), 2);
+        assert_eq!(map.cells_idxs_of(center, 4.), vec![IVec2::new(-981, 5020)]);
+        assert_eq!(
+            map.entities_near(center + Vec2::new(6., 0.), 3.),
+            vec![Entity::from_raw(0)]
+        );
+        map.remove(center, 4., Entity::from_raw(0));
+        assert_eq!(map.map.len(), 1);
+    }
+
+    #[test]
+    fn particle_bigger_than_a_cell_is_in_every_cell_it_overlaps() {
+        let mut map =
+            PositionHashMap::new(CELL_SIZE, Vec2::new(MIN_X, MIN_Y), Periodicity::default());
+        let center = Vec2::new(5., 5.);
+        map.insert(center, 25., Entity::from_raw(0));
+        let cells = map.cells_idxs_of(center, 25.);
+        assert_eq!(cells.len(), 36);
+        assert!(cells.contains(&IVec2::new(18, 18)));
+        assert!(cells.contains(&IVec2::new(23, 23)));
+        assert!(map
+            .entities_near(Vec2::new(29., 5.), 0.5)
+            .contains(&Entity::from_raw(0)));
+    }
+}
+
+#[cfg(test)]
+mod fluid_query_tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::super::{query::FluidQuery, *};
+
+    fn world_with_particles(centers: &[(Vec2, f32)], periodicity: Periodicity) -> World {
+        let mut world = World::new();
+        world.insert_resource(PositionHashMap::new(
+            6,
+            Vec2::new(MIN_X, MIN_Y),
+            periodicity,
+        ));
+        world.insert_resource(EntityPreviousPositionMap {
+            map: HashMap::new(),
+        });
+        world.insert_resource(periodicity);
+        for (center, radius) in centers {
+            world.spawn((
+                FluidParticle {
+                    radius: *radius,
+                    restitution_coeff: 1.,
+                    friction_coeff: 0.,
+                },
+                Transform::from_translation(center.extend(0.)),
+            ));
+        }
+        world.run_system_once(update_position_map).unwrap();
+        world
+    }
+
+    fn query<T: 'static>(
+        world: &mut World,
+        f: impl Fn(FluidQuery) -> T + Send + Sync + 'static,
+    ) -> T {
+        world
+            .run_system_once(move |fluid_query: FluidQuery| f(fluid_query))
+            .unwrap()
+    }
+
+    fn distances(result: Vec<(Entity, f32)>) -> Vec<f32> {
+        result.into_iter().map(|(_, distance)| distance).collect()
+    }
+
+    #[test]
+    fn within_radius_and_in_rect_keep_only_the_particles_inside() {
+        let mut world = world_with_particles(
+            &[
+                (Vec2::new(0., 0.), 3.),
+                (Vec2::new(10., 0.), 3.),
+                (Vec2::new(0., -20.), 3.),
+                (Vec2::new(50., 50.), 3.),
+            ],
+            Periodicity::default(),
+        );
+        assert_eq!(
+            distances(query(&mut world, |fluid_query| {
+                fluid_query.within_radius(Vec2::new(1., 0.), 20.)
+            })),
+            vec![1., 9.]
+        );
+        assert_eq!(
+            distances(query(&mut world, |fluid_query| {
+                fluid_query.in_rect(Rect::new(-5., -25., 5., 5.))
+            })),
+            vec![10., 10.]
+        );
+    }
+
+    #[test]
+    fn nearest_k_searches_past_the_first_cells() {
+        let mut world = world_with_particles(
+            &[
+                (Vec2::new(0., 0.), 3.),
+                (Vec2::new(150., 0.), 3.),
+                (Vec2::new(-180., 0.), 3.),
+            ],
+            Periodicity::default(),
+        );
+        assert_eq!(
+            distances(query(&mut world, |fluid_query| {
+                fluid_query.nearest_k(Vec2::new(10., 0.), 2)
+            })),
+            vec![10., 140.]
+        );
+        assert_eq!(
+            query(&mut world, |fluid_query| fluid_query
+                .nearest_k(Vec2::ZERO, 5)
+                .len()),
+            3
+        );
+    }
+
+    #[test]
+    fn raycast_hits_the_particles_along_the_ray_in_order() {
+        let mut world = world_with_particles(
+            &[
+                (Vec2::new(100., 0.), 5.),
+                (Vec2::new(40., 2.), 3.),
+                (Vec2::new(40., 20.), 3.),
+                (Vec2::new(-40., 0.), 3.),
+            ],
+            Periodicity::default(),
+        );
+        let hits = distances(query(&mut world, |fluid_query| {
+            fluid_query.raycast(Ray2d::new(Vec2::ZERO, Dir2::X), f32::INFINITY)
+        }));
+        assert_eq!(hits.len(), 2);
+        assert!((hits[0] - (40. - 5f32.sqrt())).abs() < 1e-4);
+        assert!((hits[1] - 95.).abs() < 1e-4);
+    }
+
+    #[test]
+    fn raycast_goes_through_the_periodic_sides() {
+        let mut world = world_with_particles(
+            &[(Vec2::new(-190., 0.), 3.)],
+            Periodicity { x: true, y: false },
+        );
+        let hits = distances(query(&mut world, |fluid_query| {
+            fluid_query.raycast(Ray2d::new(Vec2::new(180., 0.), Dir2::X), 100.)
+        }));
+        assert_eq!(hits.len(), 1);
+        assert!((hits[0] - 27.).abs() < 1e-4);
+    }
+
+    #[test]
+    fn unbounded_raycast_stops_going_around_the_periodic_sides() {
+        let mut world = world_with_particles(
+            &[(Vec2::new(-190., 0.), 3.)],
+            Periodicity { x: true, y: true },
+        );
+        let direction = Dir2::new(Vec2::new(1., 0.1)).unwrap();
+        let hits = distances(query(&mut world, move |fluid_query| {
+            fluid_query.raycast(Ray2d::new(Vec2::new(180., 0.), direction), f32::INFINITY)
+        }));
+        assert!(hits
+            .iter()
+            .all(|distance| distance.is_finite() && *distance <= 4. * 400. * 2f32.sqrt()));
+    }
+
+    #[test]
+    fn unbounded_areas_find_every_particle() {
+        let mut world = world_with_particles(
+            &[(Vec2::new(0., 0.), 3.), (Vec2::new(-150., 120.), 3.)],
+            Periodicity::default(),
+        );
+        let within_radius = query(&mut world, |fluid_query| {
+            fluid_query.within_radius(Vec2::ZERO, f32::INFINITY)
+        });
+        assert_eq!(distances(within_radius).len(), 2);
+        let in_rect = query(&mut world, |fluid_query| {
+            fluid_query.in_rect(Rect::new(-1e30, -1e30, 1e30, 1e30))
+        });
+        assert_eq!(distances(in_rect).len(), 2);
+        let nan_radius = query(&mut world, |fluid_query| {
+            fluid_query.within_radius(Vec2::ZERO, f32::NAN)
+        });
+        assert!(nan_radius.is_empty());
     }
 }
@@ -0,0 +1,380 @@
+mod tests;
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use bevy::{
+    prelude::*,
+    tasks::{ComputeTaskPool, ParallelSlice},
+};
+
+use super::UnorderedEntitiesPair;
+use crate::kinetics::bounds::{periodic::Periodicity, MAX_X, MAX_Y, MIN_X, MIN_Y};
+
+/// Particle as stored in the `SortedGrid`.
+#[derive(Clone, Copy, Debug)]
+pub struct GridParticle {
+    pub entity: Entity,
+    pub center: Vec2,
+    pub radius: f32,
+}
+
+/// Broad phase grids rebuilt from scratch every step: the particles are
+/// counting sorted by the cell of their center into one contiguous array, so
+/// that the particles of a cell are next to each other in memory.
+///
+/// The particles are split into levels of radii within a factor of two of
+/// each other, each with its own grid, so that a few big particles do not
+/// make the cells of the small ones big too.
+#[derive(Resource, Default)]
+pub struct SortedGrid {
+    periodicity: Periodicity,
+    /// From the smallest particles to the biggest ones.
+    levels: Vec<GridLevel>,
+}
+
+/// Grid of the particles of one size class. Its cells are at least as wide
+/// as its biggest particle, so that two of its particles overlapping each
+/// other are in the same cell or in neighbouring ones.
+#[derive(Default)]
+struct GridLevel {
+    cell_size: Vec2,
+    /// Corner of the first cell.
+    origin: Vec2,
+    /// Amount of cells along each axis.
+    dimensions: UVec2,
+    periodicity: Periodicity,
+    max_radius: f32,
+    /// Index in `particles` of the first particle of every cell, followed by
+    /// the amount of particles.
+    cell_starts: Vec<u32>,
+    particles: Vec<GridParticle>,
+}
+
+/// Largest amount of cells per particle. Scattered particles get bigger
+/// cells rather than a grid as big as the area they span.
+const MAX_CELLS_PER_PARTICLE: u64 = 4;
+
+/// Neighbours visited from every cell, half of them so that every pair of
+/// neighbouring cells is visited once.
+const HALF_STENCIL: [IVec2; 4] = [
+    IVec2::new(1, 0),
+    IVec2::new(-1, 1),
+    IVec2::new(0, 1),
+    IVec2::new(1, 1),
+];
+
+impl SortedGrid {
+    pub fn rebuild(
+        &mut self,
+        particles: impl IntoIterator<Item = GridParticle>,
+        periodicity: Periodicity,
+    ) {
+        let mut particles: Vec<GridParticle> = particles.into_iter().collect();
+        for particle in particles.iter_mut() {
+            particle.center = periodicity.wrap(particle.center);
+        }
+        self.periodicity = periodicity;
+
+        let min_radius = particles
+            .iter()
+            .map(|particle| particle.radius)
+            .filter(|radius| *radius > 0.)
+            .fold(f32::MAX, f32::min);
+        let level_of = |particle: &GridParticle| {
+            (particle.radius / min_radius).log2().floor().max(0.) as usize
+        };
+        let amount_of_levels = particles
+            .iter()
+            .map(level_of)
+            .max()
+            .map_or(0, |level| level + 1);
+        let mut level_particles: Vec<Vec<GridParticle>> = vec![vec![]; amount_of_levels];
+        for particle in particles {
+            level_particles[level_of(&particle)].push(particle);
+        }
+        // Empty levels are dropped, the remaining ones stay sorted by size.
+        level_particles.retain(|particles| !particles.is_empty());
+        self.levels
+            .resize_with(level_particles.len(), GridLevel::default);
+        for (level, particles) in self.levels.iter_mut().zip(level_particles) {
+            level.rebuild(particles, periodicity);
+        }
+    }
+
+    /// Every pair of overlapping particles, once, along with the amount of
+    /// pairs that were checked. Each level is checked against itself, then
+    /// its particles are looked up in the grids of the bigger particles.
+    pub(super) fn overlapping_pairs(&self) -> (Vec<UnorderedEntitiesPair>, usize) {
+        let mut overlapping_pairs = vec![];
+        let mut amount_of_checked_pairs = 0;
+        for (idx, level) in self.levels.iter().enumerate() {
+            let (level_pairs, checked) = level.overlapping_pairs();
+            overlapping_pairs.extend(level_pairs);
+            amount_of_checked_pairs += checked;
+            for bigger_level in &self.levels[idx + 1..] {
+                let (level_pairs, checked) = bigger_level.overlapping_pairs_with(&level.particles);
+                overlapping_pairs.extend(level_pairs);
+                amount_of_checked_pairs += checked;
+            }
+        }
+        (overlapping_pairs, amount_of_checked_pairs)
+    }
+}
+
+impl GridLevel {
+    fn rebuild(&mut self, unsorted: Vec<GridParticle>, periodicity: Periodicity) {
+        let task_pool = ComputeTaskPool::get();
+        self.periodicity = periodicity;
+        self.fit(&unsorted);
+
+        let amount_of_cells = (self.dimensions.x * self.dimensions.y) as usize;
+        let counts: Vec<AtomicU32> = (0..amount_of_cells).map(|_| AtomicU32::new(0)).collect();
+        let cells: Vec<u32> = unsorted
+            .par_splat_map(task_pool, None, |_, chunk| {
+                chunk
+                    .iter()
+                    .map(|particle| {
+                        let cell = self.cell_of(particle.center);
+                        counts[cell as usize].fetch_add(1, Ordering::Relaxed);
+                        cell
+                    })
+                    .collect::<Vec<u32>>()
+            })
+            .into_iter()
+            .flatten()
+            .collect();
+
+        self.cell_starts.clear();
+        self.cell_starts.push(0);
+        let mut start = 0;
+        for count in counts {
+            start += count.into_inner();
+            self.cell_starts.push(start);
+        }
+
+        // Scattered in the order of the particles so that every cell lists
+        // its particles in the same order from one run to the next.
+        let mut next: Vec<u32> = self.cell_starts[..amount_of_cells].to_vec();
+        self.particles.clear();
+        self.particles.resize(
+            unsorted.len(),
+            GridParticle {
+                entity: Entity::PLACEHOLDER,
+                center: Vec2::ZERO,
+                radius: 0.,
+            },
+        );
+        for (particle, cell) in unsorted.into_iter().zip(cells) {
+            self.particles[next[cell as usize] as usize] = particle;
+            next[cell as usize] += 1;
+        }
+    }
+
+    /// Sizes and places the cells around `particles`, spanning the whole
+    /// domain along the periodic axes.
+    fn fit(&mut self, particles: &[GridParticle]) {
+        let max_radius = particles
+            .iter()
+            .fold(0f32, |max_radius, particle| max_radius.max(particle.radius));
+        self.max_radius = max_radius;
+        let (min, max) = particles.iter().fold(
+            (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+            |(min, max), particle| (min.min(particle.center), max.max(particle.center)),
+        );
+        let (min, max) = if particles.is_empty() {
+            (Vec2::ZERO, Vec2::ZERO)
+        } else {
+            (min, max)
+        };
+        let periodic = BVec2::new(self.periodicity.x, self.periodicity.y);
+        self.origin = Vec2::select(periodic, Vec2::new(MIN_X, MIN_Y), min);
+        let period = Vec2::new(MAX_X - MIN_X, MAX_Y - MIN_Y);
+        let extent = Vec2::select(periodic, period, max - min);
+
+        let max_amount_of_cells = (particles.len() as u64 * MAX_CELLS_PER_PARTICLE).max(1);
+        let mut cell_size = (2. * max_radius).max(1.);
+        loop {
+            // Along periodic axes the cells are stretched to tile the domain,
+            // and there are at least three of them so that the stencil never
+            // reaches a cell twice.
+            let dimensions = Vec2::select(
+                periodic,
+                (period / cell_size).floor().max(Vec2::splat(3.)),
+                (extent / cell_size).floor() + 1.,
+            )
+            .as_uvec2();
+            if dimensions.x as u64 * dimensions.y as u64 <= max_amount_of_cells
+                || cell_size >= extent.max_element()
+            {
+                self.dimensions = dimensions;
+                self.cell_size = Vec2::select(
+                    periodic,
+                    period / dimensions.as_vec2(),
+                    Vec2::splat(cell_size),
+                );
+                return;
+            }
+            cell_size *= 2.;
+        }
+    }
+
+    fn cell_of(&self, position: Vec2) -> u32 {
+        let cell = ((position - self.origin) / self.cell_size)
+            .floor()
+            .as_ivec2()
+            .clamp(IVec2::ZERO, self.dimensions.as_ivec2() - 1)
+            .as_uvec2();
+        cell.y * self.dimensions.x + cell.x
+    }
+
+    /// Index of the cell `step` cells away from `cell`, wrapped around along
+    /// the periodic axes.
+    fn neighbour_of(&self, cell: UVec2, step: IVec2) -> Option<u32> {
+        let dimensions = self.dimensions.as_ivec2();
+        let mut neighbour = cell.as_ivec2() + step;
+        if self.periodicity.x {
+            neighbour.x = neighbour.x.rem_euclid(dimensions.x);
+        }
+        if self.periodicity.y {
+            neighbour.y = neighbour.y.rem_euclid(dimensions.y);
+        }
+        if neighbour.cmplt(IVec2::ZERO).any() || neighbour.cmpge(dimensions).any() {
+            return None;
+        }
+        Some(neighbour.y as u32 * self.dimensions.x + neighbour.x as u32)
+    }
+
+    fn particles_in(&self, cell: u32) -> &[GridParticle] {
+        let (start, end) = (
+            self.cell_starts[cell as usize],
+            self.cell_starts[cell as usize + 1],
+        );
+        &self.particles[start as usize..end as usize]
+    }
+
+    /// Every pair of overlapping particles of this level, once, along with
+    /// the amount of pairs that were checked. The cells are split between the
+    /// threads and each of them only looks at half of its neighbours.
+    fn overlapping_pairs(&self) -> (Vec<UnorderedEntitiesPair>, usize) {
+        let amount_of_cells = self.cell_starts.len().saturating_sub(1);
+        if amount_of_cells == 0 {
+            return (vec![], 0);
+        }
+        let task_pool = ComputeTaskPool::get();
+        let chunk_size = amount_of_cells.div_ceil(task_pool.thread_num() * 4).max(1);
+        let cell_starts = &self.cell_starts[..amount_of_cells];
+        let detections = cell_starts.par_chunk_map(task_pool, chunk_size, |chunk_idx, chunk| {
+            let mut overlapping_pairs = vec![];
+            let mut amount_of_checked_pairs = 0;
+            let first_cell = chunk_idx * chunk_size;
+            for cell in first_cell..first_cell + chunk.len() {
+                let cell = cell as u32;
+                let particles = self.particles_in(cell);
+                for (idx, particle1) in particles.iter().enumerate() {
+                    for particle2 in &particles[idx + 1..] {
+                        amount_of_checked_pairs += 1;
+                        if self.overlap(particle1, particle2) {
+                            overlapping_pairs.push(UnorderedEntitiesPair::new(
+                                particle1.entity,
+                                particle2.entity,
+                            ));
+                        }
+                    }
+                }
+                let position = UVec2::new(cell % self.dimensions.x, cell / self.dimensions.x);
+                for step in HALF_STENCIL {
+                    let Some(neighbour) = self.neighbour_of(position, step) else {
+                        continue;
+                    };
+                    for particle1 in particles {
+                        for particle2 in self.particles_in(neighbour) {
+                            amount_of_checked_pairs += 1;
+                            if self.overlap(particle1, particle2) {
+                                overlapping_pairs.push(UnorderedEntitiesPair::new(
+                                    particle1.entity,
+                                    particle2.entity,
+                                ));
+                            }
+                        }
+                    }
+                }
+            }
+            (overlapping_pairs, amount_of_checked_pairs)
+        });
+        let amount_of_checked_pairs = detections.iter().map(|(_, checked)| checked).sum();
+        let overlapping_pairs = detections
+            .into_iter()
+            .flat_map(|(overlapping_pairs, _)| overlapping_pairs)
+            .collect();
+        (overlapping_pairs, amount_of_checked_pairs)
+    }
+
+    /// Every pair of one of `others`, smaller particles, overlapping one of
+    /// this level, along with the amount of pairs that were checked.
+    fn overlapping_pairs_with(
+        &self,
+        others: &[GridParticle],
+    ) -> (Vec<UnorderedEntitiesPair>, usize) {
+        let task_pool = ComputeTaskPool::get();
+        let chunk_size = others.len().div_ceil(task_pool.thread_num() * 4).max(1);
+        let detections = others.par_chunk_map(task_pool, chunk_size, |_, chunk| {
+            let mut overlapping_pairs = vec![];
+            let mut amount_of_checked_pairs = 0;
+            for particle1 in chunk {
+                let reach = particle1.radius + self.max_radius;
+                for cell in self.cells_between(particle1.center - reach, particle1.center + reach) {
+                    for particle2 in self.particles_in(cell) {
+                        amount_of_checked_pairs += 1;
+                        if self.overlap(particle1, particle2) {
+                            overlapping_pairs.push(UnorderedEntitiesPair::new(
+                                particle1.entity,
+                                particle2.entity,
+                            ));
+                        }
+                    }
+                }
+            }
+            (overlapping_pairs, amount_of_checked_pairs)
+        });
+        let amount_of_checked_pairs = detections.iter().map(|(_, checked)| checked).sum();
+        let overlapping_pairs = detections
+            .into_iter()
+            .flat_map(|(overlapping_pairs, _)| overlapping_pairs)
+            .collect();
+        (overlapping_pairs, amount_of_checked_pairs)
+    }
+
+    /// Cells covering the rectangle between `min` and `max`, each of them
+    /// once, wrapped around along the periodic axes and clamped onto the grid
+    /// along the other ones.
+    fn cells_between(&self, min: Vec2, max: Vec2) -> Vec<u32> {
+        let first = ((min - self.origin) / self.cell_size).floor().as_ivec2();
+        let last = ((max - self.origin) / self.cell_size).floor().as_ivec2();
+        let dimensions = self.dimensions.as_ivec2();
+        let axis_cells = |first: i32, last: i32, amount_of_cells: i32, periodic: bool| {
+            if periodic {
+                (first..first + (last - first + 1).min(amount_of_cells))
+                    .map(|cell| cell.rem_euclid(amount_of_cells))
+                    .collect::<Vec<i32>>()
+            } else {
+                (first.clamp(0, amount_of_cells - 1)..=last.clamp(0, amount_of_cells - 1)).collect()
+            }
+        };
+        let columns = axis_cells(first.x, last.x, dimensions.x, self.periodicity.x);
+        let rows = axis_cells(first.y, last.y, dimensions.y, self.periodicity.y);
+        rows.iter()
+            .flat_map(|row| {
+                columns
+                    .iter()
+                    .map(move |column| (*row * dimensions.x + *column) as u32)
+            })
+            .collect()
+    }
+
+    fn overlap(&self, particle1: &GridParticle, particle2: &GridParticle) -> bool {
+        self.periodicity
+            .offset(particle1.center, particle2.center)
+            .length()
+            <= particle1.radius + particle2.radius
+    }
+}
@@ -0,0 +1,214 @@
+#[cfg(test)]
+mod sorted_grid_tests {
+    use std::time::{Duration, Instant};
+
+    use bevy::{
+        tasks::{ComputeTaskPool, ParallelSlice, TaskPool},
+        utils::HashSet,
+    };
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::super::*;
+    use crate::kinetics::collisions::position_hashing::PositionHashMap;
+
+    fn random_particles(amount: usize, min: Vec2, max: Vec2) -> Vec<GridParticle> {
+        let mut rng = StdRng::seed_from_u64(7);
+        (0..amount)
+            .map(|idx| GridParticle {
+                entity: Entity::from_raw(idx as u32),
+                center: Vec2::new(rng.gen_range(min.x..max.x), rng.gen_range(min.y..max.y)),
+                radius: rng.gen_range(1.0..5.),
+            })
+            .collect()
+    }
+
+    fn brute_force_pairs(
+        particles: &[GridParticle],
+        periodicity: Periodicity,
+    ) -> HashSet<UnorderedEntitiesPair> {
+        let mut pairs = HashSet::new();
+        for (idx, particle1) in particles.iter().enumerate() {
+            for particle2 in &particles[idx + 1..] {
+                if periodicity
+                    .offset(particle1.center, particle2.center)
+                    .length()
+                    <= particle1.radius + particle2.radius
+                {
+                    pairs.insert(UnorderedEntitiesPair::new(
+                        particle1.entity,
+                        particle2.entity,
+                    ));
+                }
+            }
+        }
+        pairs
+    }
+
+    fn assert_finds_every_pair_once(particles: Vec<GridParticle>, periodicity: Periodicity) {
+        ComputeTaskPool::get_or_init(TaskPool::default);
+        let expected = brute_force_pairs(&particles, periodicity);
+        let mut grid = SortedGrid::default();
+        grid.rebuild(particles, periodicity);
+        let (pairs, _) = grid.overlapping_pairs();
+        let found: HashSet<UnorderedEntitiesPair> = pairs.iter().copied().collect();
+        assert_eq!(found.len(), pairs.len(), "a pair was found twice");
+        assert!(found == expected);
+    }
+
+    #[test]
+    fn finds_the_same_pairs_as_brute_force() {
+        assert_finds_every_pair_once(
+            random_particles(800, Vec2::new(-200., -200.), Vec2::new(200., 200.)),
+            Periodicity::default(),
+        );
+    }
+
+    #[test]
+    fn finds_the_pairs_across_the_periodic_sides() {
+        assert_finds_every_pair_once(
+            random_particles(800, Vec2::new(-200., -200.), Vec2::new(200., 200.)),
+            Periodicity { x: true, y: true },
+        );
+    }
+
+    #[test]
+    fn scattered_particles_far_outside_the_domain_get_bigger_cells() {
+        let mut particles = random_particles(300, Vec2::new(-50., -50.), Vec2::new(50., 50.));
+        particles.extend(random_particles(
+            3,
+            Vec2::new(90_000., -90_000.),
+            Vec2::new(100_000., -80_000.),
+        ));
+        for (idx, particle) in particles.iter_mut().enumerate() {
+            particle.entity = Entity::from_raw(idx as u32);
+        }
+        assert_finds_every_pair_once(particles, Periodicity { x: false, y: true });
+    }
+
+    #[test]
+    fn finds_the_pairs_between_particles_of_very_different_sizes() {
+        let mut particles = random_particles(600, Vec2::new(-200., -200.), Vec2::new(200., 200.));
+        let mut rng = StdRng::seed_from_u64(11);
+        for particle in particles.iter_mut() {
+            particle.radius = match rng.gen_range(0..10) {
+                0 => rng.gen_range(20.0..60.),
+                1..=3 => rng.gen_range(4.0..10.),
+                _ => rng.gen_range(0.2..1.),
+            };
+        }
+        assert_finds_every_pair_once(particles.clone(), Periodicity::default());
+        assert_finds_every_pair_once(particles, Periodicity { x: true, y: false });
+    }
+
+    /// Particles of radius 3 packed on a jittered lattice, slightly closer
+    /// than their diameter so that neighbours overlap.
+    fn lattice_particles(amount: usize, rng: &mut StdRng) -> Vec<GridParticle> {
+        let side = (amount as f32).sqrt().ceil() as usize;
+        (0..amount)
+            .map(|idx| GridParticle {
+                entity: Entity::from_raw(idx as u32),
+                center: Vec2::new(MIN_X, MIN_Y)
+                    + Vec2::new((idx % side) as f32, (idx / side) as f32) * 5.5
+                    + Vec2::new(rng.gen_range(-0.5..0.5), rng.gen_range(-0.5..0.5)),
+                radius: 3.,
+            })
+            .collect()
+    }
+
+    /// Broad phase the grid replaced: every cell of the `PositionHashMap` is
+    /// checked against itself, and a `HashSet` drops the pairs found in
+    /// several cells.
+    fn hash_set_cells_pairs(
+        position_hash_map: &PositionHashMap,
+        particles: &[GridParticle],
+    ) -> (HashSet<UnorderedEntitiesPair>, usize) {
+        let cell_sets: Vec<_> = position_hash_map.map.values().collect();
+        let detections = cell_sets.par_splat_map(ComputeTaskPool::get(), None, |_, slice| {
+            let mut amount_of_checked_pairs = 0;
+            let mut colliding_pairs = HashSet::<UnorderedEntitiesPair>::new();
+            for &cell_set in slice {
+                for entity1 in cell_set {
+                    for entity2 in cell_set {
+                        amount_of_checked_pairs += 1;
+                        let pair = UnorderedEntitiesPair::new(*entity1, *entity2);
+                        if entity1 == entity2 || colliding_pairs.contains(&pair) {
+                            continue;
+                        }
+                        let (particle1, particle2) = (
+                            &particles[entity1.index() as usize],
+                            &particles[entity2.index() as usize],
+                        );
+                        if particle1.center.distance(particle2.center)
+                            <= particle1.radius + particle2.radius
+                        {
+                            colliding_pairs.insert(pair);
+                        }
+                    }
+                }
+            }
+            (colliding_pairs, amount_of_checked_pairs)
+        });
+        let amount_of_checked_pairs = detections.iter().map(|(_, amount)| amount).sum();
+        let colliding_pairs = detections
+            .into_iter()
+            .flat_map(|(colliding_pairs, _)| colliding_pairs)
+            .collect();
+        (colliding_pairs, amount_of_checked_pairs)
+    }
+
+    /// Times steps of both broad phases, pair search plus overlap tests, on
+    /// particles that move slightly every step, and checks that the grid is
+    /// at least twice as fast. The hash map is updated between the steps and
+    /// outside of the timing, as it was by `update_position_map`, while the
+    /// grid is rebuilt within it. The first step, which allocates the grid,
+    /// isn't timed. The timings are printed with
+    /// `cargo test --release sorted_grid_is_faster -- --nocapture`.
+    #[test]
+    fn sorted_grid_is_faster_than_the_hash_set_cells() {
+        ComputeTaskPool::get_or_init(TaskPool::default);
+        const STEPS: u32 = 5;
+        let mut rng = StdRng::seed_from_u64(3);
+        for amount in [3_000, 30_000] {
+            let mut particles = lattice_particles(amount, &mut rng);
+            let mut grid = SortedGrid::default();
+            let (mut hash_set_time, mut grid_time) = (Duration::ZERO, Duration::ZERO);
+            for step in 0..=STEPS {
+                for particle in particles.iter_mut() {
+                    particle.center +=
+                        Vec2::new(rng.gen_range(-0.1..0.1), rng.gen_range(-0.1..0.1));
+                }
+                let mut position_hash_map =
+                    PositionHashMap::new(6, Vec2::new(MIN_X, MIN_Y), Periodicity::default());
+                for particle in &particles {
+                    position_hash_map.insert_area(
+                        particle.center - particle.radius,
+                        particle.center + particle.radius,
+                        particle.entity,
+                    );
+                }
+
+                let start = Instant::now();
+                let (expected, _) = hash_set_cells_pairs(&position_hash_map, &particles);
+                let hash_set_step_time = start.elapsed();
+
+                let start = Instant::now();
+                grid.rebuild(particles.iter().copied(), Periodicity::default());
+                let (pairs, _) = grid.overlapping_pairs();
+                let grid_step_time = start.elapsed();
+
+                if step > 0 {
+                    hash_set_time += hash_set_step_time;
+                    grid_time += grid_step_time;
+                }
+
+                assert_eq!(pairs.len(), expected.len());
+            }
+            let (hash_set_time, grid_time) = (hash_set_time / STEPS, grid_time / STEPS);
+            let speedup = hash_set_time.as_secs_f64() / grid_time.as_secs_f64();
+            println!(
+                "{amount} particles: HashSet cells {hash_set_time:.2?}, sorted grid {grid_time:.2?}, {speedup:.1}x"
+            );
+            assert!(speedup >= 2., "{amount} particles: {speedup:.1}x");
+        }
+    }
+}
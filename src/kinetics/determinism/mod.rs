@@ -0,0 +1,71 @@
+mod tests;
+
+use bevy::prelude::*;
+
+use super::velocity::Velocity;
+use crate::fluids::particle::FluidParticle;
+
+/// Makes the steps reproducible: the contacts are solved in the order of
+/// their entities rather than in the order they are found in, which depends
+/// on the layout of the broad phase grid, and a `StateChecksum` is computed
+/// after every step to compare runs.
+///
+/// The rest of the step already gives the same results whatever the amount
+/// of threads: the parallel systems only write to their own particle, the
+/// parallel searches keep the order of their chunks and the neighbours come
+/// sorted out of the `PositionHashMap`. Runs starting from the same state and
+/// seeds stay identical to the bit as long as nothing outside `FixedUpdate`,
+/// like the controls, changes the particles.
+///
+/// Turned on by `KineticsPlugin::deterministic`, which the binary sets with
+/// `--deterministic`.
+#[derive(Resource, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct DeterministicMode(pub bool);
+
+pub fn is_deterministic(deterministic_mode: Res<DeterministicMode>) -> bool {
+    deterministic_mode.0
+}
+
+/// Hash of the positions and velocities of every particle at the end of the
+/// last `FixedUpdate` in `DeterministicMode`.
+#[derive(Resource, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct StateChecksum {
+    /// Amount of steps checksummed so far.
+    pub step: u64,
+    pub value: u64,
+}
+
+pub fn update_state_checksum(
+    mut state_checksum: ResMut<StateChecksum>,
+    particles_q: Query<(Entity, &Transform, &Velocity), With<FluidParticle>>,
+) {
+    state_checksum.step += 1;
+    state_checksum.value = checksum(
+        particles_q
+            .iter()
+            .map(|(entity, transform, velocity)| (entity, transform.translation.xy(), velocity.0)),
+    );
+}
+
+/// FNV-1a hash of the bits of the positions and velocities, taken in the
+/// order of the entities so that it does not depend on the query order. The
+/// entities themselves are left out, as their ids shift with whatever else
+/// got spawned first.
+pub fn checksum(particles: impl IntoIterator<Item = (Entity, Vec2, Vec2)>) -> u64 {
+    let mut particles: Vec<(Entity, Vec2, Vec2)> = particles.into_iter().collect();
+    particles.sort_unstable_by_key(|(entity, ..)| *entity);
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for (_, position, velocity) in particles {
+        let words = [
+            position.x.to_bits() as u64,
+            position.y.to_bits() as u64,
+            velocity.x.to_bits() as u64,
+            velocity.y.to_bits() as u64,
+        ];
+        for byte in words.iter().flat_map(|word| word.to_le_bytes()) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
@@ -0,0 +1,188 @@
+#[cfg(test)]
+mod determinism_tests {
+    use std::{process::Command, time::Duration};
+
+    use bevy::{
+        core::{TaskPoolOptions, TaskPoolPlugin},
+        ecs::system::RunSystemOnce,
+        state::app::StatesPlugin,
+        tasks::{ComputeTaskPool, TaskPool},
+        time::TimeUpdateStrategy,
+    };
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::super::*;
+    use crate::{
+        kinetics::{
+            bounds::periodic::Periodicity,
+            collisions::{
+                apply_collisions,
+                contact_solver::{ContactCache, ContactSolverSettings},
+                sorted_grid::SortedGrid,
+            },
+            mass::Mass,
+            KineticsPlugin,
+        },
+        performance_monitor::{CollisionDetectionMonitor, PerformanceMonitorPlugin},
+    };
+
+    #[test]
+    fn checksum_depends_on_every_bit_but_not_on_the_order() {
+        let particles = [
+            (Entity::from_raw(0), Vec2::new(1., 2.), Vec2::new(0.5, 0.)),
+            (Entity::from_raw(1), Vec2::new(-3., 4.), Vec2::new(0., -1.)),
+        ];
+        let reversed = [particles[1], particles[0]];
+        assert_eq!(checksum(particles), checksum(reversed));
+
+        let mut nudged = particles;
+        nudged[1].1.x = f32::from_bits(nudged[1].1.x.to_bits() + 1);
+        assert_ne!(checksum(particles), checksum(nudged));
+    }
+
+    #[derive(Component)]
+    struct OtherArchetype;
+
+    /// Particles piled on top of each other, half of them in another
+    /// archetype when `split` so that the queries visit them in another
+    /// order.
+    fn world_with_pile(split: bool) -> World {
+        let mut world = World::new();
+        let mut time = Time::<()>::default();
+        time.advance_by(Duration::from_secs_f32(1. / 144.));
+        world.insert_resource(time);
+        world.insert_resource(CollisionDetectionMonitor {
+            duration: Duration::ZERO,
+            checked_pairs: 0,
+            colliding_pairs: 0,
+        });
+        world.insert_resource(Periodicity::default());
+        world.insert_resource(DeterministicMode(true));
+        world.insert_resource(ContactSolverSettings::default());
+        world.init_resource::<ContactCache>();
+        world.init_resource::<SortedGrid>();
+
+        let mut rng = StdRng::seed_from_u64(40);
+        for idx in 0..40 {
+            let mut particle = world.spawn((
+                FluidParticle {
+                    radius: 3.,
+                    restitution_coeff: 0.5,
+                    friction_coeff: 0.3,
+                },
+                Transform::from_xyz(rng.gen_range(-12.0..12.), rng.gen_range(-12.0..12.), 0.),
+                Mass(1.),
+                Velocity(Vec2::new(rng.gen_range(-1.0..1.), rng.gen_range(-1.0..1.))),
+            ));
+            if split && idx % 2 == 0 {
+                particle.insert(OtherArchetype);
+            }
+        }
+        world
+    }
+
+    fn checksum_of(world: &mut World) -> u64 {
+        let mut particles_q = world.query::<(Entity, &Transform, &Velocity)>();
+        checksum(
+            particles_q
+                .iter(world)
+                .map(|(entity, transform, velocity)| {
+                    (entity, transform.translation.xy(), velocity.0)
+                }),
+        )
+    }
+
+    #[test]
+    fn contacts_give_the_same_state_whatever_order_they_are_found_in() {
+        ComputeTaskPool::get_or_init(TaskPool::default);
+        let mut world = world_with_pile(false);
+        let mut split_world = world_with_pile(true);
+        for _ in 0..5 {
+            world.run_system_once(apply_collisions).unwrap();
+            split_world.run_system_once(apply_collisions).unwrap();
+        }
+        assert!(
+            world
+                .resource::<CollisionDetectionMonitor>()
+                .colliding_pairs
+                > 0
+        );
+        assert_eq!(checksum_of(&mut world), checksum_of(&mut split_world));
+    }
+
+    /// Amount of threads of the task pools in `stepped_scene_checksum`.
+    const THREADS_VAR: &str = "DETERMINISM_TEST_THREADS";
+    const CHECKSUM_LABEL: &str = "state checksum: ";
+
+    /// Steps the default scene headless in `DeterministicMode`, with task
+    /// pools of `THREADS_VAR` threads, and prints its `StateChecksum`. The
+    /// task pools are global to the process, so every amount of threads needs
+    /// a process of its own, started by
+    /// `kinetics_step_is_the_same_on_any_amount_of_threads`.
+    #[test]
+    #[ignore]
+    fn stepped_scene_checksum() {
+        let threads = std::env::var(THREADS_VAR)
+            .ok()
+            .and_then(|threads| threads.parse().ok())
+            .unwrap_or(1);
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins.set(TaskPoolPlugin {
+                task_pool_options: TaskPoolOptions::with_num_threads(threads),
+            }),
+            AssetPlugin::default(),
+            StatesPlugin,
+        ))
+        .init_asset::<Mesh>()
+        .init_asset::<ColorMaterial>()
+        .init_asset::<Image>()
+        .init_resource::<ButtonInput<KeyCode>>()
+        .add_plugins((
+            crate::controls::ControlsPlugin,
+            KineticsPlugin {
+                deterministic: true,
+                ..default()
+            },
+            crate::draw::DrawPlugin,
+            PerformanceMonitorPlugin,
+            crate::sources::SourcesPlugin,
+        ))
+        .insert_resource(Time::<Fixed>::from_hz(144.))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            1. / 144.,
+        )));
+        for _ in 0..30 {
+            app.update();
+        }
+        let state_checksum = app.world().resource::<StateChecksum>();
+        assert!(state_checksum.step > 0);
+        println!("{CHECKSUM_LABEL}{:016x}", state_checksum.value);
+    }
+
+    #[test]
+    fn kinetics_step_is_the_same_on_any_amount_of_threads() {
+        let test_name = concat!(module_path!(), "::stepped_scene_checksum")
+            .split_once("::")
+            .unwrap()
+            .1;
+        let checksums: Vec<String> = [1, 4]
+            .into_iter()
+            .map(|threads| {
+                let output = Command::new(std::env::current_exe().unwrap())
+                    .args([test_name, "--exact", "--ignored", "--nocapture"])
+                    .env(THREADS_VAR, threads.to_string())
+                    .output()
+                    .unwrap();
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                assert!(output.status.success(), "{stdout}");
+                stdout
+                    .lines()
+                    .find_map(|line| Some(line.split_once(CHECKSUM_LABEL)?.1))
+                    .unwrap_or_else(|| panic!("no checksum in {stdout}"))
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(checksums[0], checksums[1]);
+    }
+}
@@ -0,0 +1,315 @@
+mod tests;
+
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use super::{
+    forces::{ForceSource, Forces},
+    mass::Mass,
+    obstacles::shape::ObstacleShape,
+    velocity::Velocity,
+};
+use crate::{controls::toggle_force_fields::ForceFieldsToggled, fluids::particle::FluidParticle};
+
+/// External force acting on the particles inside its region, placed by its
+/// `Transform`. The fields overlapping a particle add up.
+#[derive(Component, Clone, Debug)]
+pub struct ForceField {
+    pub kind: FieldKind,
+    /// Where the field acts, everywhere if `None`.
+    pub region: Option<ObstacleShape>,
+    pub falloff: Falloff,
+    /// Multiplies the whole field.
+    pub strength: f32,
+    pub animation: FieldAnimation,
+}
+
+impl ForceField {
+    pub fn new(kind: FieldKind) -> ForceField {
+        ForceField {
+            kind,
+            region: None,
+            falloff: Falloff::None,
+            strength: 1.,
+            animation: FieldAnimation::default(),
+        }
+    }
+}
+
+/// What a `ForceField` does, in accelerations in m/s². Directions are in the
+/// local frame of the field.
+#[derive(Clone, Copy, Debug)]
+pub enum FieldKind {
+    /// The same acceleration everywhere, like a wind.
+    Uniform { acceleration: Vec2 },
+    /// Towards the center of the field, away from it if negative.
+    Radial { acceleration: f32 },
+    /// Around the center of the field, counterclockwise if positive.
+    Vortex { acceleration: f32 },
+    /// Curl of a noise potential, which stirs the particles without
+    /// gathering them anywhere.
+    Turbulence {
+        acceleration: f32,
+        /// Size, in pixels, of the eddies, at least `MIN_TURBULENCE_SCALE`.
+        scale: f32,
+        /// Eddies crossed per second as the noise scrolls.
+        speed: f32,
+        seed: u32,
+    },
+    /// Against the velocity, with `linear * v + quadratic * |v| * v`.
+    Drag { linear: f32, quadratic: f32 },
+}
+
+/// Smallest size, in pixels, of the eddies of `FieldKind::Turbulence`. Below
+/// it the noise is sampled too coarsely to be smooth, and a zero scale would
+/// divide by zero.
+pub const MIN_TURBULENCE_SCALE: f32 = 1.;
+
+/// How a `ForceField` fades out towards the border of its region.
+#[derive(Clone, Copy, Debug)]
+pub enum Falloff {
+    /// Full strength up to the border.
+    None,
+    /// From zero at the border to full strength `width` pixels inside. A
+    /// width of zero or less is a hard step at the border.
+    Linear { width: f32 },
+    /// Like `Linear`, with a smoothstep that doesn't kink at either end.
+    Smooth { width: f32 },
+}
+
+/// Changes of a `ForceField` over time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FieldAnimation {
+    /// Period, in seconds, of a strength going from full to
+    /// `1 - pulse_depth` and back. No pulse if zero.
+    pub pulse_period: f32,
+    pub pulse_depth: f32,
+    /// Turns the field around its center, in radians per second.
+    pub angular_speed: f32,
+}
+
+impl FieldAnimation {
+    fn strength_at(&self, time: f32) -> f32 {
+        if self.pulse_period <= 0. {
+            return 1.;
+        }
+        1. - self.pulse_depth * 0.5 * (1. - (TAU * time / self.pulse_period).cos())
+    }
+}
+
+/// A field with its `Transform` and animation applied at some instant.
+#[derive(Clone, Copy)]
+struct PlacedField<'a> {
+    field: &'a ForceField,
+    center: Vec2,
+    /// Rotation of the field at that instant.
+    rotation: Rot2,
+    /// Rotation of its region, which doesn't spin.
+    region_rotation: Rot2,
+    strength: f32,
+    time: f32,
+}
+
+impl<'a> PlacedField<'a> {
+    fn new(field: &'a ForceField, transform: &Transform, time: f32) -> PlacedField<'a> {
+        let region_rotation = Rot2::radians(transform.rotation.to_euler(EulerRot::ZYX).0);
+        PlacedField {
+            field,
+            center: transform.translation.xy(),
+            rotation: region_rotation * Rot2::radians(field.animation.angular_speed * time),
+            region_rotation,
+            strength: field.strength * field.animation.strength_at(time),
+            time,
+        }
+    }
+
+    /// Acceleration, in m/s², of a particle at `position` in pixels moving at
+    /// `velocity` in m/s.
+    fn acceleration_at(&self, position: Vec2, velocity: Vec2) -> Vec2 {
+        let offset = position - self.center;
+        let weight = match &self.field.region {
+            None => 1.,
+            Some(region) => {
+                let (distance, _) = region.signed_distance(self.region_rotation.inverse() * offset);
+                self.field.falloff.weight(-distance)
+            }
+        };
+        if weight <= 0. {
+            return Vec2::ZERO;
+        }
+        let acceleration = match self.field.kind {
+            FieldKind::Uniform { acceleration } => self.rotation * acceleration,
+            FieldKind::Radial { acceleration } => -offset.normalize_or_zero() * acceleration,
+            FieldKind::Vortex { acceleration } => offset.normalize_or_zero().perp() * acceleration,
+            FieldKind::Turbulence {
+                acceleration,
+                scale,
+                speed,
+                seed,
+            } => {
+                let local = self.rotation.inverse() * offset / scale.max(MIN_TURBULENCE_SCALE)
+                    + Vec2::new(1., 0.5) * speed * self.time;
+                self.rotation * curl_noise(local, seed) * acceleration
+            }
+            FieldKind::Drag { linear, quadratic } => {
+                -velocity * (linear + quadratic * velocity.length())
+            }
+        };
+        acceleration * self.strength * weight
+    }
+}
+
+impl Falloff {
+    /// Strength, between zero and one, `depth` pixels inside the region.
+    fn weight(&self, depth: f32) -> f32 {
+        if depth < 0. {
+            return 0.;
+        }
+        match *self {
+            Falloff::None => 1.,
+            Falloff::Linear { width } | Falloff::Smooth { width } if width <= 0. => 1.,
+            Falloff::Linear { width } => (depth / width).min(1.),
+            Falloff::Smooth { width } => {
+                let t = (depth / width).min(1.);
+                t * t * (3. - 2. * t)
+            }
+        }
+    }
+}
+
+pub fn spawn_force_fields(mut commands: Commands) {
+    let mut wind = ForceField::new(FieldKind::Uniform {
+        acceleration: Vec2::new(6., 0.),
+    });
+    wind.region = Some(ObstacleShape::Rectangle {
+        half_size: Vec2::new(200., 40.),
+    });
+    wind.falloff = Falloff::Smooth { width: 20. };
+    wind.animation.pulse_period = 4.;
+    wind.animation.pulse_depth = 1.;
+    commands.spawn((wind, Transform::from_xyz(0., 120., 0.)));
+
+    let mut vortex = ForceField::new(FieldKind::Vortex { acceleration: 8. });
+    vortex.region = Some(ObstacleShape::Circle { radius: 70. });
+    vortex.falloff = Falloff::Linear { width: 40. };
+    commands.spawn((vortex, Transform::from_xyz(-80., -120., 0.)));
+
+    let mut attractor = ForceField::new(FieldKind::Radial { acceleration: 10. });
+    attractor.region = Some(ObstacleShape::Circle { radius: 50. });
+    attractor.falloff = Falloff::Smooth { width: 50. };
+    commands.spawn((attractor, Transform::from_xyz(100., -20., 0.)));
+
+    commands.spawn((
+        ForceField::new(FieldKind::Turbulence {
+            acceleration: 3.,
+            scale: 60.,
+            speed: 0.3,
+            seed: 40,
+        }),
+        Transform::default(),
+    ));
+    commands.spawn((
+        ForceField::new(FieldKind::Drag {
+            linear: 0.05,
+            quadratic: 0.02,
+        }),
+        Transform::default(),
+    ));
+}
+
+/// Adds the acceleration of every `ForceField` to the `Forces` of the
+/// particles. The fields are taken in the order of their entities so that
+/// their sum doesn't depend on the query order.
+pub fn apply_force_fields(
+    time: Res<Time>,
+    fields_q: Query<(Entity, &ForceField, &Transform)>,
+    mut particles_q: Query<(&Transform, &Mass, &Velocity, &mut Forces), With<FluidParticle>>,
+) {
+    let elapsed = time.elapsed_secs();
+    let mut fields: Vec<(Entity, PlacedField)> = fields_q
+        .iter()
+        .map(|(entity, field, transform)| (entity, PlacedField::new(field, transform, elapsed)))
+        .collect();
+    if fields.is_empty() {
+        return;
+    }
+    fields.sort_unstable_by_key(|(entity, _)| *entity);
+
+    particles_q.par_iter_mut().for_each(
+        |(transform, Mass(mass), Velocity(velocity), mut forces)| {
+            let acceleration: Vec2 = fields
+                .iter()
+                .map(|(_, field)| field.acceleration_at(transform.translation.xy(), *velocity))
+                .sum();
+            if acceleration != Vec2::ZERO {
+                forces.add(ForceSource::User, mass * acceleration);
+            }
+        },
+    );
+}
+
+pub fn draw_force_fields(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    fields_q: Query<(Entity, &ForceField), Added<ForceField>>,
+) {
+    for (entity, field) in fields_q.iter() {
+        let Some(region) = &field.region else {
+            continue;
+        };
+        commands.entity(entity).insert((
+            Mesh2d(meshes.add(region)),
+            MeshMaterial2d(materials.add(Color::hsla(200., 0.8, 0.6, 0.15))),
+        ));
+    }
+}
+
+pub fn show_force_fields(
+    force_fields_toggled: Res<ForceFieldsToggled>,
+    mut fields_q: Query<&mut Visibility, With<ForceField>>,
+) {
+    for mut visibility in fields_q.iter_mut() {
+        *visibility = if force_fields_toggled.0 {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+/// Divergence-free noise: the curl of a smooth scalar noise, around unit
+/// magnitude.
+fn curl_noise(point: Vec2, seed: u32) -> Vec2 {
+    const EPSILON: f32 = 1e-2;
+    let dx =
+        value_noise(point + Vec2::X * EPSILON, seed) - value_noise(point - Vec2::X * EPSILON, seed);
+    let dy =
+        value_noise(point + Vec2::Y * EPSILON, seed) - value_noise(point - Vec2::Y * EPSILON, seed);
+    Vec2::new(dy, -dx) / (2. * EPSILON)
+}
+
+/// Smooth noise between -1 and 1, interpolating random values placed on the
+/// integer lattice.
+fn value_noise(point: Vec2, seed: u32) -> f32 {
+    let cell = point.floor();
+    let t = point - cell;
+    let t = t * t * (3. - 2. * t);
+    let cell = cell.as_ivec2();
+    let corner = |dx: i32, dy: i32| lattice_value(cell + IVec2::new(dx, dy), seed);
+    let bottom = corner(0, 0).lerp(corner(1, 0), t.x);
+    let top = corner(0, 1).lerp(corner(1, 1), t.x);
+    bottom.lerp(top, t.y)
+}
+
+fn lattice_value(cell: IVec2, seed: u32) -> f32 {
+    let mut hash = (cell.x as u32)
+        .wrapping_mul(0x8da6_b343)
+        .wrapping_add((cell.y as u32).wrapping_mul(0xd816_3841))
+        .wrapping_add(seed.wrapping_mul(0xcb1a_b31f));
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x2c1b_3c6d);
+    hash ^= hash >> 12;
+    hash as f32 / u32::MAX as f32 * 2. - 1.
+}
@@ -0,0 +1,112 @@
+#[cfg(test)]
+mod force_fields_tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::super::*;
+
+    #[test]
+    fn falloff_ramps_up_from_the_border() {
+        assert_eq!(Falloff::None.weight(-1.), 0.);
+        assert_eq!(Falloff::None.weight(0.), 1.);
+        assert_eq!(Falloff::Linear { width: 10. }.weight(5.), 0.5);
+        assert_eq!(Falloff::Smooth { width: 10. }.weight(5.), 0.5);
+        assert!(Falloff::Smooth { width: 10. }.weight(1.) < 0.1);
+        assert_eq!(Falloff::Smooth { width: 10. }.weight(30.), 1.);
+    }
+
+    #[test]
+    fn falloff_without_a_width_is_a_hard_step() {
+        for falloff in [Falloff::Linear { width: 0. }, Falloff::Smooth { width: 0. }] {
+            assert_eq!(falloff.weight(-1.), 0., "{falloff:?}");
+            assert_eq!(falloff.weight(0.), 1., "{falloff:?}");
+            assert_eq!(falloff.weight(5.), 1., "{falloff:?}");
+        }
+    }
+
+    #[test]
+    fn curl_noise_has_no_divergence() {
+        let epsilon = 1e-2;
+        for idx in 0..20 {
+            let point = Vec2::new(idx as f32 * 0.37, idx as f32 * -0.61);
+            let divergence = (curl_noise(point + Vec2::X * epsilon, 40).x
+                - curl_noise(point - Vec2::X * epsilon, 40).x
+                + curl_noise(point + Vec2::Y * epsilon, 40).y
+                - curl_noise(point - Vec2::Y * epsilon, 40).y)
+                / (2. * epsilon);
+            assert!(divergence.abs() < 0.05, "{divergence}");
+        }
+    }
+
+    #[test]
+    fn overlapping_fields_add_up_inside_their_regions() {
+        let mut world = World::new();
+        let mut time = Time::<()>::default();
+        time.advance_by(Duration::from_secs(1));
+        world.insert_resource(time);
+
+        let mut wind = ForceField::new(FieldKind::Uniform {
+            acceleration: Vec2::new(2., 0.),
+        });
+        wind.region = Some(ObstacleShape::Rectangle {
+            half_size: Vec2::new(50., 10.),
+        });
+        world.spawn((wind, Transform::default()));
+        // Turned a quarter turn, so pushing up.
+        let mut pulsing_wind = ForceField::new(FieldKind::Uniform {
+            acceleration: Vec2::new(1., 0.),
+        });
+        pulsing_wind.animation.pulse_period = 2.;
+        pulsing_wind.animation.pulse_depth = 0.5;
+        world.spawn((
+            pulsing_wind,
+            Transform::from_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2)),
+        ));
+        world.spawn((
+            ForceField::new(FieldKind::Drag {
+                linear: 1.,
+                quadratic: 0.,
+            }),
+            Transform::default(),
+        ));
+
+        let spawn_particle = |world: &mut World, position: Vec2| {
+            world
+                .spawn((
+                    FluidParticle {
+                        radius: 3.,
+                        restitution_coeff: 1.,
+                        friction_coeff: 0.,
+                    },
+                    Transform::from_translation(position.extend(0.)),
+                    Mass(2.),
+                    Velocity(Vec2::new(0.5, 0.)),
+                    Forces::default(),
+                ))
+                .id()
+        };
+        let inside = spawn_particle(&mut world, Vec2::new(20., 0.));
+        let outside = spawn_particle(&mut world, Vec2::new(20., 30.));
+        world.run_system_once(apply_force_fields).unwrap();
+
+        // Half way through its period, the pulsing wind is at half strength.
+        let force = |entity| world.get::<Forces>(entity).unwrap()[ForceSource::User];
+        assert!((force(inside) - 2. * Vec2::new(2. - 0.5, 0.5)).length() < 1e-5);
+        assert!((force(outside) - 2. * Vec2::new(-0.5, 0.5)).length() < 1e-5);
+    }
+
+    #[test]
+    fn turbulence_without_a_scale_stays_finite() {
+        let field = ForceField::new(FieldKind::Turbulence {
+            acceleration: 3.,
+            scale: 0.,
+            speed: 0.3,
+            seed: 40,
+        });
+        let placed = PlacedField::new(&field, &Transform::default(), 1.);
+        for position in [Vec2::ZERO, Vec2::new(12.5, -7.)] {
+            assert!(placed.acceleration_at(position, Vec2::ZERO).is_finite());
+        }
+    }
+}
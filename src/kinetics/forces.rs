@@ -1,19 +1,117 @@
+use std::{
+    ops::Index,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
 use bevy::prelude::*;
 
-use super::{acceleration::Acceleration, mass::Mass};
+use super::{acceleration::Acceleration, limiter::MotionLimits, mass::Mass};
+use crate::performance_monitor::LimiterMonitor;
+
+pub fn clear_forces(mut query: Query<&mut Forces>) {
+    query.par_iter_mut().for_each(|mut forces| forces.clear());
+}
 
-pub fn apply_forces(mut query: Query<(&mut Forces, &Mass, &mut Acceleration)>) {
+/// Clears `ForceSource::Collision`, once per step since the contacts are
+/// resolved after the last evaluation of the forces.
+pub fn clear_contact_forces(mut query: Query<&mut Forces>) {
     query
         .par_iter_mut()
-        .for_each(|(mut forces, Mass(mass), mut acceleration)| {
-            acceleration.0 =
-                (forces.0.iter().sum::<Vec2>() / mass).clamp_length_max(MAX_VELOCITY_LENGTH);
+        .for_each(|mut forces| forces.0[ForceSource::Collision as usize] = Vec2::ZERO);
+}
 
-            forces.0.clear();
+pub fn apply_forces(
+    motion_limits: Res<MotionLimits>,
+    limiter_monitor: Option<ResMut<LimiterMonitor>>,
+    mut query: Query<(&Forces, &Mass, &mut Acceleration)>,
+) {
+    let clamped = AtomicUsize::new(0);
+    query
+        .par_iter_mut()
+        .for_each(|(forces, Mass(mass), mut acceleration)| {
+            let (limited, is_clamped) = motion_limits.limit_acceleration(forces.total() / mass);
+            acceleration.0 = limited;
+            if is_clamped {
+                clamped.fetch_add(1, Ordering::Relaxed);
+            }
         });
+    if let Some(mut limiter_monitor) = limiter_monitor {
+        limiter_monitor.clamped_accelerations = limiter_monitor
+            .clamped_accelerations
+            .max(clamped.into_inner());
+    }
+}
+
+/// What a force acting on a particle comes from.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ForceSource {
+    /// Gravity and the attraction between the particles.
+    Gravity,
+    /// The contacts between the particles, as the force that would have
+    /// changed their velocity as much over the step.
+    Collision,
+    /// The container and the obstacles.
+    Boundary,
+    Pressure,
+    Viscosity,
+    /// The cohesion between the fluid kinds.
+    SurfaceTension,
+    /// The `ForceField`s.
+    User,
 }
 
-#[derive(Component, Clone)]
-pub struct Forces(pub Vec<Vec2>);
+impl ForceSource {
+    pub const ALL: [ForceSource; 7] = [
+        ForceSource::Gravity,
+        ForceSource::Collision,
+        ForceSource::Boundary,
+        ForceSource::Pressure,
+        ForceSource::Viscosity,
+        ForceSource::SurfaceTension,
+        ForceSource::User,
+    ];
+}
 
-const MAX_VELOCITY_LENGTH: f32 = 1000.;
+/// Forces acting on a particle during the current step, in newtons, summed
+/// per source. They are cleared at the start of every evaluation and kept
+/// until the next one, so they can be looked at between the steps.
+///
+/// The contacts apply impulses to the velocities instead, after the forces
+/// are evaluated. Their `ForceSource::Collision` is only there to be looked
+/// at: it is left out of `total`, kept by `clear` and cleared once per step
+/// by `clear_contact_forces`.
+#[derive(Component, Clone, Copy, Default, Debug)]
+pub struct Forces([Vec2; ForceSource::ALL.len()]);
+
+impl Forces {
+    pub fn add(&mut self, source: ForceSource, force: Vec2) {
+        self.0[source as usize] += force;
+    }
+
+    /// Sum of the forces of every source but the contacts, always added in
+    /// the same order.
+    pub fn total(&self) -> Vec2 {
+        self.iter()
+            .filter(|(source, _)| *source != ForceSource::Collision)
+            .map(|(_, force)| force)
+            .sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (ForceSource, Vec2)> + '_ {
+        ForceSource::ALL.into_iter().zip(self.0.iter().copied())
+    }
+
+    pub fn clear(&mut self) {
+        let contacts = self[ForceSource::Collision];
+        self.0 = Default::default();
+        self.0[ForceSource::Collision as usize] = contacts;
+    }
+}
+
+impl Index<ForceSource> for Forces {
+    type Output = Vec2;
+
+    fn index(&self, source: ForceSource) -> &Vec2 {
+        &self.0[source as usize]
+    }
+}
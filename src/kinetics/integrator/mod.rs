@@ -0,0 +1,167 @@
+mod tests;
+
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*, utils::HashMap};
+
+use super::{
+    acceleration::Acceleration,
+    bounds::periodic::Periodicity,
+    collisions::{continuous, position_hashing::update_position_map},
+    velocity::{displace, Velocity},
+};
+use crate::controls::toggle_continuous_collisions::ContinuousCollisionsToggled;
+
+/// Systems that turn the current state of the particles into
+/// `Acceleration`s. Depending on the `Integrator` it runs once or several
+/// times per `FixedUpdate`.
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EvaluateForces;
+
+/// Systems that resolve the contacts by changing the velocities directly. It
+/// runs once per step, right before the positions are advanced with the
+/// velocities that include all the forces.
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ResolveContacts;
+
+/// The time integration scheme used by `Solver::Forces`.
+#[derive(Resource, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Integrator {
+    /// Kick with the new forces, then drift with the new velocity.
+    #[default]
+    SemiImplicitEuler,
+    /// Half kick, drift, force evaluation, half kick.
+    VelocityVerlet,
+    /// Half drift, force evaluation, kick, half drift.
+    Leapfrog,
+    /// Classic fourth order Runge-Kutta, evaluating the forces four times.
+    /// The particles are hashed again at every stage, so that the SPH forces
+    /// find the neighbours of the stage positions, but the stages are not
+    /// swept for continuous collisions.
+    Rk4,
+}
+
+pub fn integrate(world: &mut World) {
+    let delta = world.resource::<Time>().delta().as_secs_f32();
+    match *world.resource::<Integrator>() {
+        Integrator::SemiImplicitEuler => {
+            world.run_schedule(EvaluateForces);
+            kick(world, delta);
+            world.run_schedule(ResolveContacts);
+            drift(world, delta);
+        }
+        Integrator::VelocityVerlet => {
+            // The accelerations are still the ones evaluated at the end of the
+            // previous step.
+            kick(world, delta / 2.);
+            world.run_schedule(ResolveContacts);
+            drift(world, delta);
+            world.run_schedule(EvaluateForces);
+            kick(world, delta / 2.);
+        }
+        Integrator::Leapfrog => {
+            drift(world, delta / 2.);
+            world.run_schedule(EvaluateForces);
+            kick(world, delta);
+            world.run_schedule(ResolveContacts);
+            drift(world, delta / 2.);
+        }
+        Integrator::Rk4 => runge_kutta(world, delta),
+    }
+}
+
+fn kick(world: &mut World, delta: f32) {
+    world
+        .query::<(&Acceleration, &mut Velocity)>()
+        .par_iter_mut(world)
+        .for_each(|(Acceleration(acceleration), mut velocity)| {
+            velocity.0 += acceleration * delta;
+        });
+}
+
+fn drift(world: &mut World, delta: f32) {
+    if world.resource::<ContinuousCollisionsToggled>().0 {
+        continuous::advance(world, delta);
+        return;
+    }
+    let periodicity = *world.resource::<Periodicity>();
+    world
+        .query::<(&Velocity, &mut Transform)>()
+        .par_iter_mut(world)
+        .for_each(|(Velocity(velocity), mut transform)| {
+            displace(&mut transform, velocity * delta, &periodicity);
+        });
+}
+
+fn runge_kutta(world: &mut World, delta: f32) {
+    // The initial state is taken after the contacts are resolved and the
+    // first evaluation, so the corrections they make to the positions are kept.
+    world.run_schedule(ResolveContacts);
+    world.run_schedule(EvaluateForces);
+
+    let mut state_q = world.query::<(Entity, &Transform, &Velocity, &Acceleration)>();
+    let mut stages: HashMap<Entity, RungeKuttaState> = state_q
+        .iter(world)
+        .map(
+            |(entity, transform, Velocity(velocity), Acceleration(acceleration))| {
+                (
+                    entity,
+                    RungeKuttaState {
+                        initial_translation: transform.translation,
+                        initial_velocity: *velocity,
+                        last_derivative: (*velocity, *acceleration),
+                        weighted_derivatives: (*velocity, *acceleration),
+                    },
+                )
+            },
+        )
+        .collect();
+
+    let periodicity = *world.resource::<Periodicity>();
+    let mut update_q = world.query::<(Entity, &mut Transform, &mut Velocity)>();
+    for (stage_delta, weight) in [(delta / 2., 2.), (delta / 2., 2.), (delta, 1.)] {
+        for (entity, mut transform, mut velocity) in update_q.iter_mut(world) {
+            if let Some(stage) = stages.get(&entity) {
+                transform.translation = stage.initial_translation;
+                displace(
+                    &mut transform,
+                    stage.last_derivative.0 * stage_delta,
+                    &periodicity,
+                );
+                velocity.0 = stage.initial_velocity + stage.last_derivative.1 * stage_delta;
+            }
+        }
+
+        world
+            .run_system_cached(update_position_map)
+            .expect("the position maps are initialized at startup");
+        world.run_schedule(EvaluateForces);
+
+        for (entity, _, Velocity(velocity), Acceleration(acceleration)) in state_q.iter(world) {
+            if let Some(stage) = stages.get_mut(&entity) {
+                stage.last_derivative = (*velocity, *acceleration);
+                stage.weighted_derivatives.0 += weight * velocity;
+                stage.weighted_derivatives.1 += weight * acceleration;
+            }
+        }
+    }
+
+    for (entity, mut transform, mut velocity) in update_q.iter_mut(world) {
+        if let Some(stage) = stages.get(&entity) {
+            transform.translation = stage.initial_translation;
+            displace(
+                &mut transform,
+                stage.weighted_derivatives.0 * delta / 6.,
+                &periodicity,
+            );
+            velocity.0 = stage.initial_velocity + stage.weighted_derivatives.1 * delta / 6.;
+        }
+    }
+}
+
+struct RungeKuttaState {
+    initial_translation: Vec3,
+    initial_velocity: Vec2,
+    /// Velocity and acceleration of the latest stage.
+    last_derivative: (Vec2, Vec2),
+    /// Running `k1 + 2 * k2 + 2 * k3 + k4` sums.
+    weighted_derivatives: (Vec2, Vec2),
+}
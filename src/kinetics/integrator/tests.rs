@@ -0,0 +1,141 @@
+#[cfg(test)]
+mod integrator_tests {
+    use std::{f32::consts::TAU, time::Duration};
+
+    use super::super::*;
+    use crate::{
+        fluids::particle::FluidParticle,
+        kinetics::{
+            collisions::position_hashing::PositionHashingPlugin,
+            forces::{clear_contact_forces, clear_forces, ForceSource, Forces},
+            velocity::PIXELS_PER_METER,
+        },
+    };
+
+    /// Angular frequency of the spring, for a period of one second.
+    const OMEGA: f32 = TAU;
+
+    fn pull_back(mut query: Query<(&Transform, &mut Acceleration)>) {
+        for (transform, mut acceleration) in query.iter_mut() {
+            acceleration.0 = -OMEGA * OMEGA * transform.translation.xy() / PIXELS_PER_METER;
+        }
+    }
+
+    /// Largest change of the energy of a particle on a spring over ten
+    /// periods, relative to its initial energy.
+    fn energy_drift(integrator: Integrator) -> f32 {
+        let mut app = App::new();
+        app.add_plugins(PositionHashingPlugin)
+            .add_systems(EvaluateForces, pull_back)
+            .init_schedule(ResolveContacts)
+            .insert_resource(integrator)
+            .insert_resource(Periodicity::default())
+            .insert_resource(ContinuousCollisionsToggled(false));
+        let mut time = Time::<()>::default();
+        time.advance_by(Duration::from_secs_f32(1. / 144.));
+        app.insert_resource(time);
+        app.world_mut().run_schedule(Startup);
+
+        let entity = app
+            .world_mut()
+            .spawn((
+                FluidParticle {
+                    radius: 3.,
+                    restitution_coeff: 1.,
+                    friction_coeff: 0.,
+                },
+                Transform::from_xyz(PIXELS_PER_METER, 0., 0.),
+                Velocity(Vec2::ZERO),
+                Acceleration(Vec2::ZERO),
+            ))
+            .id();
+        let world = app.world_mut();
+        world.run_schedule(EvaluateForces);
+
+        let energy = |world: &World| {
+            let position =
+                world.get::<Transform>(entity).unwrap().translation.xy() / PIXELS_PER_METER;
+            let velocity = world.get::<Velocity>(entity).unwrap().0;
+            0.5 * velocity.length_squared() + 0.5 * OMEGA * OMEGA * position.length_squared()
+        };
+        let initial_energy = energy(world);
+        (0..1440)
+            .map(|_| {
+                integrate(world);
+                (energy(world) - initial_energy).abs() / initial_energy
+            })
+            .fold(0., f32::max)
+    }
+
+    #[test]
+    fn semi_implicit_euler_keeps_the_energy_bounded() {
+        let drift = energy_drift(Integrator::SemiImplicitEuler);
+        assert!(drift < 0.05, "{drift}");
+    }
+
+    #[test]
+    fn second_order_integrators_keep_the_energy() {
+        for integrator in [Integrator::VelocityVerlet, Integrator::Leapfrog] {
+            let drift = energy_drift(integrator);
+            assert!(drift < 1e-3, "{integrator:?}: {drift}");
+        }
+    }
+
+    #[test]
+    fn runge_kutta_keeps_the_energy() {
+        let drift = energy_drift(Integrator::Rk4);
+        assert!(drift < 1e-4, "{drift}");
+    }
+
+    fn bump(mut query: Query<&mut Forces>) {
+        for mut forces in query.iter_mut() {
+            forces.add(ForceSource::Collision, Vec2::X);
+        }
+    }
+
+    #[test]
+    fn contact_forces_last_the_whole_step() {
+        for integrator in [
+            Integrator::SemiImplicitEuler,
+            Integrator::VelocityVerlet,
+            Integrator::Leapfrog,
+            Integrator::Rk4,
+        ] {
+            let mut app = App::new();
+            app.add_plugins(PositionHashingPlugin)
+                .add_systems(EvaluateForces, clear_forces)
+                .add_systems(ResolveContacts, bump)
+                .insert_resource(integrator)
+                .insert_resource(Periodicity::default())
+                .insert_resource(ContinuousCollisionsToggled(false));
+            let mut time = Time::<()>::default();
+            time.advance_by(Duration::from_secs_f32(1. / 144.));
+            app.insert_resource(time);
+            app.world_mut().run_schedule(Startup);
+
+            let entity = app
+                .world_mut()
+                .spawn((
+                    FluidParticle {
+                        radius: 3.,
+                        restitution_coeff: 1.,
+                        friction_coeff: 0.,
+                    },
+                    Transform::default(),
+                    Velocity(Vec2::ZERO),
+                    Acceleration(Vec2::ZERO),
+                    Forces::default(),
+                ))
+                .id();
+            let world = app.world_mut();
+            for _ in 0..2 {
+                world.run_system_cached(clear_contact_forces).unwrap();
+                integrate(world);
+            }
+
+            let forces = world.get::<Forces>(entity).unwrap();
+            assert_ne!(forces[ForceSource::Collision], Vec2::ZERO, "{integrator:?}");
+            assert_eq!(forces.total(), Vec2::ZERO, "{integrator:?}");
+        }
+    }
+}
@@ -0,0 +1,82 @@
+mod tests;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use bevy::prelude::*;
+
+use super::velocity::Velocity;
+use crate::{fluids::particle::FluidParticle, performance_monitor::LimiterMonitor};
+
+/// Bounds on the accelerations and velocities of the particles, which keep
+/// violent collisions from blowing the simulation up at the cost of some
+/// energy. Validation runs should turn them off with `enabled`.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct MotionLimits {
+    pub enabled: bool,
+    /// In m/s², unbounded if `None`.
+    pub max_acceleration: Option<f32>,
+    /// In m/s, unbounded if `None`.
+    pub max_velocity: Option<f32>,
+}
+
+impl Default for MotionLimits {
+    fn default() -> Self {
+        MotionLimits {
+            enabled: true,
+            max_acceleration: Some(1000.),
+            max_velocity: None,
+        }
+    }
+}
+
+impl MotionLimits {
+    /// `acceleration` shortened to the limit if needed, and whether it was.
+    pub fn limit_acceleration(&self, acceleration: Vec2) -> (Vec2, bool) {
+        self.limit(acceleration, self.max_acceleration)
+    }
+
+    /// `velocity` shortened to the limit if needed, and whether it was.
+    pub fn limit_velocity(&self, velocity: Vec2) -> (Vec2, bool) {
+        self.limit(velocity, self.max_velocity)
+    }
+
+    fn limit(&self, vector: Vec2, max_length: Option<f32>) -> (Vec2, bool) {
+        match max_length {
+            Some(max_length)
+                if self.enabled && vector.length_squared() > max_length * max_length =>
+            {
+                (vector.clamp_length_max(max_length), true)
+            }
+            _ => (vector, false),
+        }
+    }
+}
+
+/// Counts the clamped particles anew for every `FixedUpdate`.
+pub fn reset_limiter_monitor(limiter_monitor: Option<ResMut<LimiterMonitor>>) {
+    if let Some(mut limiter_monitor) = limiter_monitor {
+        *limiter_monitor = LimiterMonitor::default();
+    }
+}
+
+pub fn limit_velocities(
+    motion_limits: Res<MotionLimits>,
+    limiter_monitor: Option<ResMut<LimiterMonitor>>,
+    mut particles_q: Query<&mut Velocity, With<FluidParticle>>,
+) {
+    if !motion_limits.enabled || motion_limits.max_velocity.is_none() {
+        return;
+    }
+    let clamped = AtomicUsize::new(0);
+    particles_q.par_iter_mut().for_each(|mut velocity| {
+        let (limited, is_clamped) = motion_limits.limit_velocity(velocity.0);
+        if is_clamped {
+            velocity.0 = limited;
+            clamped.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+    if let Some(mut limiter_monitor) = limiter_monitor {
+        limiter_monitor.clamped_velocities =
+            limiter_monitor.clamped_velocities.max(clamped.into_inner());
+    }
+}
@@ -0,0 +1,41 @@
+#[cfg(test)]
+mod motion_limits_tests {
+    use super::super::*;
+
+    #[test]
+    fn only_vectors_over_the_limit_are_clamped() {
+        let motion_limits = MotionLimits {
+            enabled: true,
+            max_acceleration: Some(10.),
+            max_velocity: None,
+        };
+        assert_eq!(
+            motion_limits.limit_acceleration(Vec2::new(6., 8.)),
+            (Vec2::new(6., 8.), false)
+        );
+        let (limited, is_clamped) = motion_limits.limit_acceleration(Vec2::new(30., 40.));
+        assert!(is_clamped);
+        assert!((limited - Vec2::new(6., 8.)).length() < 1e-5);
+        assert_eq!(
+            motion_limits.limit_velocity(Vec2::new(300., 400.)),
+            (Vec2::new(300., 400.), false)
+        );
+    }
+
+    #[test]
+    fn disabled_limits_leave_everything_through() {
+        let motion_limits = MotionLimits {
+            enabled: false,
+            max_acceleration: Some(10.),
+            max_velocity: Some(1.),
+        };
+        assert_eq!(
+            motion_limits.limit_acceleration(Vec2::new(30., 40.)),
+            (Vec2::new(30., 40.), false)
+        );
+        assert_eq!(
+            motion_limits.limit_velocity(Vec2::new(3., 4.)),
+            (Vec2::new(3., 4.), false)
+        );
+    }
+}
@@ -2,35 +2,211 @@ pub mod acceleration;
 pub mod attraction;
 pub mod bounds;
 pub mod collisions;
+pub mod determinism;
+pub mod force_fields;
 pub mod forces;
 pub mod gravity;
+pub mod integrator;
+pub mod limiter;
 pub mod mass;
+pub mod obstacles;
+pub mod substeps;
 pub mod velocity;
 
 use bevy::prelude::*;
 
-use crate::controls::toggle_gravity::GravityToggled;
+use crate::{
+    controls::{
+        toggle_attraction::is_attraction_toggled,
+        toggle_force_fields::{is_force_fields_toggled, ForceFieldsToggled},
+        toggle_gravity::is_gravity_toggled,
+        toggle_obstacles::{is_obstacles_toggled, ObstaclesToggled},
+    },
+    fluids::{
+        self,
+        flip::FlipSettings,
+        kinds::{FluidKinds, Phases},
+        position_based::PbfSettings,
+        SphSettings,
+    },
+};
+use attraction::AttractionSettings;
+use bounds::{periodic::Periodicity, Container};
+use collisions::{
+    contact_solver::{ContactCache, ContactSolverSettings},
+    position_hashing::PositionHashingSet,
+    sorted_grid::SortedGrid,
+};
+use determinism::{DeterministicMode, StateChecksum};
+use integrator::{EvaluateForces, Integrator, ResolveContacts};
+use limiter::MotionLimits;
+use substeps::{AdaptiveTimeStep, KineticsStep};
 
-pub struct KineticsPlugin;
+#[derive(Default)]
+pub struct KineticsPlugin {
+    pub solver: Solver,
+    pub integrator: Integrator,
+    pub container: Container,
+    pub periodicity: Periodicity,
+    pub phases: Phases,
+    pub deterministic: bool,
+}
+
+/// The way particles are advanced every `FixedUpdate`, chosen at startup.
+#[derive(Resource, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Solver {
+    /// SPH and collision forces integrated with the chosen `Integrator`.
+    #[default]
+    Forces,
+    /// Position Based Fluids: density constraints solved on predicted positions.
+    PositionBasedFluids,
+    /// No particles, an Eulerian grid advecting velocity and dye instead.
+    StableFluids,
+    /// Particles carry the velocity, the pressure is solved on a grid
+    /// (PIC/FLIP blend or APIC, see `FlipSettings`).
+    Flip,
+}
 
 impl Plugin for KineticsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(collisions::position_hashing::PositionHashingPlugin)
-            .add_systems(Startup, bounds::draw_bounds)
-            .add_systems(
-                FixedUpdate,
+        app.add_plugins((
+            collisions::position_hashing::PositionHashingPlugin,
+            fluids::stable_fluids::StableFluidsPlugin,
+        ))
+        .insert_resource(self.solver)
+        .insert_resource(self.integrator)
+        .insert_resource(self.container)
+        .insert_resource(self.periodicity)
+        .insert_resource(self.phases)
+        .insert_resource(FluidKinds::new(self.phases))
+        .insert_resource(AdaptiveTimeStep::default())
+        .insert_resource(SphSettings::default())
+        .insert_resource(PbfSettings::default())
+        .insert_resource(FlipSettings::default())
+        .insert_resource(ContactSolverSettings::default())
+        .insert_resource(AttractionSettings::default())
+        .insert_resource(MotionLimits::default())
+        .init_resource::<ContactCache>()
+        .init_resource::<SortedGrid>()
+        .insert_resource(DeterministicMode(self.deterministic))
+        .init_resource::<StateChecksum>()
+        .add_systems(Startup, bounds::spawn_boundary)
+        .add_systems(
+            Update,
+            (
+                bounds::select_container.run_if(resource_changed::<Container>),
+                bounds::draw_bounds,
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            fluids::kinds::select_fluid_kinds.run_if(resource_changed::<Phases>),
+        )
+        .add_systems(
+            Startup,
+            obstacles::spawn_obstacles.run_if(not(resource_equals(Solver::StableFluids))),
+        )
+        .add_systems(
+            Update,
+            (
+                obstacles::draw_obstacles,
+                obstacles::show_obstacles.run_if(resource_changed::<ObstaclesToggled>),
+            )
+                .chain(),
+        )
+        .add_systems(
+            Startup,
+            force_fields::spawn_force_fields.run_if(not(resource_equals(Solver::StableFluids))),
+        )
+        .add_systems(
+            Update,
+            (
+                force_fields::draw_force_fields,
+                force_fields::show_force_fields.run_if(resource_changed::<ForceFieldsToggled>),
+            )
+                .chain(),
+        )
+        .add_systems(
+            Startup,
+            fluids::flip::init_flip_grid.run_if(resource_equals(Solver::Flip)),
+        )
+        .add_systems(
+            EvaluateForces,
+            (
+                forces::clear_forces,
+                gravity::apply_gravity.run_if(is_gravity_toggled),
+                attraction::apply_attraction.run_if(is_attraction_toggled),
+                force_fields::apply_force_fields.run_if(is_force_fields_toggled),
+                fluids::density::calculate_densities,
+                fluids::pressure::calculate_pressures,
+                fluids::pressure::apply_pressure_forces,
+                fluids::viscosity::apply_viscosity_forces,
+                fluids::surface_tension::apply_cohesion_forces,
+                bounds::enforce_bounds,
+                obstacles::enforce_obstacles.run_if(is_obstacles_toggled),
+                forces::apply_forces,
+            )
+                .chain(),
+        )
+        .add_systems(ResolveContacts, collisions::apply_collisions)
+        .add_systems(
+            FixedUpdate,
+            (
+                limiter::reset_limiter_monitor,
+                substeps::run_substeps,
+                determinism::update_state_checksum.run_if(determinism::is_deterministic),
+            )
+                .chain(),
+        )
+        .add_systems(
+            KineticsStep,
+            (
+                bounds::kinematic::move_boundaries.before(PositionHashingSet),
+                obstacles::hash_obstacles.in_set(PositionHashingSet),
+            ),
+        )
+        .add_systems(
+            KineticsStep,
+            (
+                (
+                    forces::clear_contact_forces,
+                    integrator::integrate,
+                    limiter::limit_velocities,
+                )
+                    .chain()
+                    .run_if(resource_equals(Solver::Forces)),
+                (
+                    forces::clear_forces,
+                    gravity::apply_gravity.run_if(is_gravity_toggled),
+                    attraction::apply_attraction.run_if(is_attraction_toggled),
+                    force_fields::apply_force_fields.run_if(is_force_fields_toggled),
+                    obstacles::enforce_obstacles.run_if(is_obstacles_toggled),
+                    fluids::position_based::predict_positions,
+                    fluids::position_based::solve_density_constraints,
+                    fluids::position_based::update_velocities_and_positions,
+                )
+                    .chain()
+                    .run_if(resource_equals(Solver::PositionBasedFluids)),
                 (
-                    gravity::apply_gravity
-                        .run_if(|gravity_toggled: Res<GravityToggled>| gravity_toggled.0),
-                    // attraction::apply_attraction,
-                    collisions::apply_collisions,
-                    // collisions::apply_collisions_single_threaded,
+                    forces::clear_forces,
+                    gravity::apply_gravity.run_if(is_gravity_toggled),
+                    attraction::apply_attraction.run_if(is_attraction_toggled),
+                    force_fields::apply_force_fields.run_if(is_force_fields_toggled),
                     bounds::enforce_bounds,
+                    obstacles::enforce_obstacles.run_if(is_obstacles_toggled),
                     forces::apply_forces,
                     acceleration::accelerate_entities,
+                    fluids::flip::transfer_particles_to_grid,
+                    fluids::flip::solve_pressure,
+                    fluids::flip::transfer_grid_to_particles,
+                    limiter::limit_velocities,
                     velocity::move_entities,
                 )
-                    .chain(),
-            );
+                    .chain()
+                    .run_if(resource_equals(Solver::Flip)),
+            )
+                .after(PositionHashingSet),
+        );
     }
 }
@@ -0,0 +1,144 @@
+pub mod shape;
+
+use bevy::prelude::*;
+
+use super::{
+    collisions::position_hashing::PositionHashMap,
+    forces::{ForceSource, Forces},
+    mass::Mass,
+    velocity::Velocity,
+};
+use crate::{controls::toggle_obstacles::ObstaclesToggled, fluids::particle::FluidParticle};
+use shape::ObstacleShape;
+
+/// Static geometry the particles bounce off, placed by its `Transform`.
+#[derive(Component, Clone, Debug)]
+pub struct Obstacle {
+    pub shape: ObstacleShape,
+}
+
+/// Demo obstacles, only acting on the particles while `ObstaclesToggled`.
+pub fn spawn_obstacles(mut commands: Commands) {
+    commands.spawn((
+        Obstacle {
+            shape: ObstacleShape::Circle { radius: 25. },
+        },
+        Transform::from_xyz(-110., -40., 0.),
+    ));
+    commands.spawn((
+        Obstacle {
+            shape: ObstacleShape::Rectangle {
+                half_size: Vec2::new(45., 8.),
+            },
+        },
+        Transform::from_xyz(90., -110., 0.).with_rotation(Quat::from_rotation_z(0.4)),
+    ));
+    commands.spawn((
+        Obstacle {
+            shape: ObstacleShape::Capsule {
+                radius: 8.,
+                half_length: 30.,
+            },
+        },
+        Transform::from_xyz(0., 40., 0.).with_rotation(Quat::from_rotation_z(1.2)),
+    ));
+    commands.spawn((
+        Obstacle {
+            shape: ObstacleShape::Polygon {
+                vertices: vec![
+                    Vec2::new(-40., 20.),
+                    Vec2::new(0., -20.),
+                    Vec2::new(40., 20.),
+                    Vec2::new(25., 20.),
+                    Vec2::new(0., -5.),
+                    Vec2::new(-25., 20.),
+                ],
+            },
+        },
+        Transform::from_xyz(110., 100., 0.),
+    ));
+}
+
+pub fn draw_obstacles(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    obstacles_q: Query<(Entity, &Obstacle), Added<Obstacle>>,
+) {
+    for (entity, obstacle) in obstacles_q.iter() {
+        commands.entity(entity).insert((
+            Mesh2d(meshes.add(&obstacle.shape)),
+            MeshMaterial2d(materials.add(Color::hsla(0., 0., 1., 0.3))),
+        ));
+    }
+}
+
+pub fn show_obstacles(
+    obstacles_toggled: Res<ObstaclesToggled>,
+    mut obstacles_q: Query<&mut Visibility, With<Obstacle>>,
+) {
+    for mut visibility in obstacles_q.iter_mut() {
+        *visibility = if obstacles_toggled.0 {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+pub fn hash_obstacles(
+    mut position_hash_map: ResMut<PositionHashMap>,
+    obstacles_q: Query<(Entity, &Obstacle, &Transform), Added<Obstacle>>,
+) {
+    for (entity, obstacle, transform) in obstacles_q.iter() {
+        let reach = Vec2::splat(obstacle.shape.bounding_radius());
+        let center = transform.translation.xy();
+        position_hash_map.insert_area(center - reach, center + reach, entity);
+    }
+}
+
+pub fn enforce_obstacles(
+    time: Res<Time>,
+    position_hash_map: Res<PositionHashMap>,
+    obstacles_q: Query<(&Obstacle, &Transform), Without<FluidParticle>>,
+    mut particles_q: Query<(
+        &FluidParticle,
+        &mut Transform,
+        &Mass,
+        &Velocity,
+        &mut Forces,
+    )>,
+) {
+    let delta = time.delta().as_secs_f32();
+    if delta == 0. {
+        return;
+    }
+    particles_q.par_iter_mut().for_each(
+        |(particle, mut transform, Mass(mass), Velocity(velocity), mut forces)| {
+            for entity in
+                position_hash_map.entities_near(transform.translation.xy(), particle.radius)
+            {
+                let Ok((obstacle, obstacle_transform)) = obstacles_q.get(entity) else {
+                    continue;
+                };
+                let local_center = obstacle_transform.rotation.inverse()
+                    * (transform.translation - obstacle_transform.translation);
+                let (distance, local_normal) = obstacle.shape.signed_distance(local_center.xy());
+                if distance >= particle.radius {
+                    continue;
+                }
+                let normal = (obstacle_transform.rotation * local_normal.extend(0.)).xy();
+
+                let normal_velocity = velocity.dot(normal);
+                if normal_velocity < 0. {
+                    let impulse = mass * (-2. * normal_velocity * normal);
+                    forces.add(
+                        ForceSource::Boundary,
+                        impulse * particle.restitution_coeff / delta,
+                    );
+                }
+                transform.translation += ((particle.radius - distance) * normal).extend(0.);
+            }
+        },
+    );
+}
@@ -0,0 +1,193 @@
+mod tests;
+use bevy::{
+    prelude::*,
+    render::{mesh::Indices, render_asset::RenderAssetUsages, render_resource::PrimitiveTopology},
+};
+
+/// Geometry of an `Obstacle`, in pixels and relative to its `Transform`.
+#[derive(Clone, Debug)]
+pub enum ObstacleShape {
+    Circle {
+        radius: f32,
+    },
+    Rectangle {
+        half_size: Vec2,
+    },
+    /// Segment along the local y axis, rounded by `radius`.
+    Capsule {
+        radius: f32,
+        half_length: f32,
+    },
+    /// Simple polygon, convex or not, with its vertices in either winding order.
+    Polygon {
+        vertices: Vec<Vec2>,
+    },
+}
+
+impl ObstacleShape {
+    /// Distance from `point` to the surface, negative inside the shape, and
+    /// the outward normal of the closest part of the surface.
+    pub fn signed_distance(&self, point: Vec2) -> (f32, Vec2) {
+        match self {
+            ObstacleShape::Circle { radius } => (
+                point.length() - radius,
+                point.try_normalize().unwrap_or(Vec2::Y),
+            ),
+            ObstacleShape::Rectangle { half_size } => {
+                let offset = point.abs() - *half_size;
+                if offset.x > 0. || offset.y > 0. {
+                    let outside = offset.max(Vec2::ZERO);
+                    (outside.length(), (outside * point.signum()).normalize())
+                } else if offset.x > offset.y {
+                    (offset.x, Vec2::new(point.x.signum(), 0.))
+                } else {
+                    (offset.y, Vec2::new(0., point.y.signum()))
+                }
+            }
+            ObstacleShape::Capsule {
+                radius,
+                half_length,
+            } => {
+                let closest = Vec2::new(0., point.y.clamp(-half_length, *half_length));
+                let offset = point - closest;
+                (
+                    offset.length() - radius,
+                    offset.try_normalize().unwrap_or(Vec2::X),
+                )
+            }
+            ObstacleShape::Polygon { vertices } => {
+                let mut closest = (f32::MAX, Vec2::Y);
+                let mut inside = false;
+                for (start, end) in edges(vertices) {
+                    let edge = end - start;
+                    let along = ((point - start).dot(edge) / edge.length_squared()).clamp(0., 1.);
+                    let offset = point - (start + along * edge);
+                    if offset.length() < closest.0 {
+                        // A point lying on the edge gets the normal of the edge.
+                        let normal = offset
+                            .try_normalize()
+                            .unwrap_or(edge.perp().normalize() * -winding(vertices));
+                        closest = (offset.length(), normal);
+                    }
+                    if (start.y > point.y) != (end.y > point.y)
+                        && point.x < start.x + (point.y - start.y) / (end.y - start.y) * edge.x
+                    {
+                        inside = !inside;
+                    }
+                }
+                if inside {
+                    (-closest.0, -closest.1)
+                } else {
+                    closest
+                }
+            }
+        }
+    }
+
+    /// Radius of a circle around the origin that contains the whole shape.
+    pub fn bounding_radius(&self) -> f32 {
+        match self {
+            ObstacleShape::Circle { radius } => *radius,
+            ObstacleShape::Rectangle { half_size } => half_size.length(),
+            ObstacleShape::Capsule {
+                radius,
+                half_length,
+            } => radius + half_length,
+            ObstacleShape::Polygon { vertices } => vertices
+                .iter()
+                .map(|vertex| vertex.length())
+                .fold(0., f32::max),
+        }
+    }
+}
+
+impl From<&ObstacleShape> for Mesh {
+    fn from(shape: &ObstacleShape) -> Self {
+        match shape {
+            ObstacleShape::Circle { radius } => Circle::new(*radius).into(),
+            ObstacleShape::Rectangle { half_size } => Rectangle::from_size(*half_size * 2.).into(),
+            ObstacleShape::Capsule {
+                radius,
+                half_length,
+            } => Capsule2d::new(*radius, *half_length * 2.).into(),
+            ObstacleShape::Polygon { vertices } => Mesh::new(
+                PrimitiveTopology::TriangleList,
+                RenderAssetUsages::default(),
+            )
+            .with_inserted_attribute(
+                Mesh::ATTRIBUTE_POSITION,
+                vertices
+                    .iter()
+                    .map(|vertex| [vertex.x, vertex.y, 0.])
+                    .collect::<Vec<_>>(),
+            )
+            .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0., 0., 1.]; vertices.len()])
+            .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0., 0.]; vertices.len()])
+            .with_inserted_indices(Indices::U32(triangulate(vertices))),
+        }
+    }
+}
+
+fn edges(vertices: &[Vec2]) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+    vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .map(|(start, end)| (*start, *end))
+}
+
+/// `1` for counterclockwise vertices, `-1` for clockwise ones.
+fn winding(vertices: &[Vec2]) -> f32 {
+    edges(vertices)
+        .map(|(start, end)| start.perp_dot(end))
+        .sum::<f32>()
+        .signum()
+}
+
+/// Ear clipping triangulation of a simple polygon, with counterclockwise
+/// triangles.
+fn triangulate(vertices: &[Vec2]) -> Vec<u32> {
+    let mut remaining: Vec<usize> = (0..vertices.len()).collect();
+    if winding(vertices) < 0. {
+        remaining.reverse();
+    }
+    let mut indices = vec![];
+
+    while remaining.len() > 3 {
+        let amount = remaining.len();
+        let ear = (0..amount).find(|&idx| {
+            let (previous, current, next) = (
+                vertices[remaining[(idx + amount - 1) % amount]],
+                vertices[remaining[idx]],
+                vertices[remaining[(idx + 1) % amount]],
+            );
+            (current - previous).perp_dot(next - current) > 0.
+                && remaining.iter().all(|&other| {
+                    let vertex = vertices[other];
+                    vertex == previous
+                        || vertex == current
+                        || vertex == next
+                        || !is_in_triangle(vertex, previous, current, next)
+                })
+        });
+        // Degenerate polygons have no ear left, the rest is dropped.
+        let Some(ear) = ear else {
+            break;
+        };
+        indices.extend([
+            remaining[(ear + amount - 1) % amount] as u32,
+            remaining[ear] as u32,
+            remaining[(ear + 1) % amount] as u32,
+        ]);
+        remaining.remove(ear);
+    }
+    if remaining.len() == 3 {
+        indices.extend(remaining.iter().map(|idx| *idx as u32));
+    }
+    indices
+}
+
+fn is_in_triangle(point: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+    (b - a).perp_dot(point - a) >= 0.
+        && (c - b).perp_dot(point - b) >= 0.
+        && (a - c).perp_dot(point - c) >= 0.
+}
@@ -0,0 +1,49 @@
+#[cfg(test)]
+mod obstacle_shape_tests {
+
+    use super::super::*;
+
+    fn chevron() -> ObstacleShape {
+        ObstacleShape::Polygon {
+            vertices: vec![
+                Vec2::new(-40., 20.),
+                Vec2::new(0., -20.),
+                Vec2::new(40., 20.),
+                Vec2::new(25., 20.),
+                Vec2::new(0., -5.),
+                Vec2::new(-25., 20.),
+            ],
+        }
+    }
+
+    #[test]
+    fn point_in_the_notch_of_a_concave_polygon_is_outside() {
+        let (distance, normal) = chevron().signed_distance(Vec2::new(0., 10.));
+        assert!(distance > 0.);
+        assert!(normal.y > 0.);
+    }
+
+    #[test]
+    fn point_inside_a_concave_polygon_has_a_negative_distance() {
+        let (distance, _) = chevron().signed_distance(Vec2::new(0., -12.));
+        assert!(distance < 0.);
+    }
+
+    #[test]
+    fn rectangle_normal_points_out_of_the_closest_side() {
+        let shape = ObstacleShape::Rectangle {
+            half_size: Vec2::new(10., 5.),
+        };
+        let (distance, normal) = shape.signed_distance(Vec2::new(2., -4.));
+        assert_eq!(distance, -1.);
+        assert_eq!(normal, Vec2::NEG_Y);
+    }
+
+    #[test]
+    fn concave_polygon_is_split_into_all_of_its_triangles() {
+        let ObstacleShape::Polygon { vertices } = chevron() else {
+            unreachable!()
+        };
+        assert_eq!(triangulate(&vertices).len(), 3 * (vertices.len() - 2));
+    }
+}
@@ -0,0 +1,88 @@
+mod tests;
+
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
+
+use super::{
+    acceleration::Acceleration,
+    velocity::{Velocity, PIXELS_PER_METER},
+};
+use crate::{fluids::particle::FluidParticle, performance_monitor::TimeStepMonitor};
+
+/// One step of the simulation. It runs one or more times per `FixedUpdate`,
+/// with `Time::delta` set to the length of the substep.
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct KineticsStep;
+
+/// Limits of the adaptive substepping.
+#[derive(Resource, Clone, Copy)]
+pub struct AdaptiveTimeStep {
+    /// Fraction of its radius a particle may travel in one substep.
+    pub cfl_number: f32,
+    pub max_substeps: u32,
+}
+
+impl Default for AdaptiveTimeStep {
+    fn default() -> Self {
+        AdaptiveTimeStep {
+            cfl_number: 0.5,
+            max_substeps: 8,
+        }
+    }
+}
+
+/// Splits the fixed time step so that no particle moves more than a fraction
+/// of its radius per substep, judging by its velocity and by the
+/// acceleration it had at the end of the previous step, and runs
+/// `KineticsStep` once per substep.
+pub fn run_substeps(world: &mut World) {
+    let fixed_time = *world.resource::<Time>();
+    let adaptive_time_step = *world.resource::<AdaptiveTimeStep>();
+
+    let stable_delta = world
+        .query::<(&FluidParticle, &Velocity, &Acceleration)>()
+        .iter(world)
+        .map(
+            |(particle, Velocity(velocity), Acceleration(acceleration))| {
+                cfl_delta(
+                    adaptive_time_step.cfl_number,
+                    particle.radius,
+                    velocity.length(),
+                    acceleration.length(),
+                )
+            },
+        )
+        .fold(f32::INFINITY, f32::min);
+
+    let substeps = ((fixed_time.delta().as_secs_f32() / stable_delta).ceil() as u32)
+        .clamp(1, adaptive_time_step.max_substeps);
+    let substep_delta = fixed_time.delta() / substeps;
+
+    let mut substep_time = Time::<()>::default();
+    substep_time.advance_to(fixed_time.elapsed() - fixed_time.delta());
+    for substep in 0..substeps {
+        let delta = if substep + 1 == substeps {
+            fixed_time.delta() - substep_delta * (substeps - 1)
+        } else {
+            substep_delta
+        };
+        substep_time.advance_by(delta);
+        *world.resource_mut::<Time>() = substep_time;
+        world.run_schedule(KineticsStep);
+    }
+    *world.resource_mut::<Time>() = fixed_time;
+
+    if let Some(mut time_step_monitor) = world.get_resource_mut::<TimeStepMonitor>() {
+        time_step_monitor.delta = substep_delta;
+        time_step_monitor.substeps = substeps as usize;
+    }
+}
+
+/// Largest time step, in seconds, in which a particle of `radius` pixels
+/// travels at most `cfl_number` of its radius, either moving at `speed` m/s
+/// or starting from rest with an `acceleration` in m/s².
+fn cfl_delta(cfl_number: f32, radius: f32, speed: f32, acceleration: f32) -> f32 {
+    let radius = radius / PIXELS_PER_METER;
+    let velocity_limit = cfl_number * radius / speed;
+    let acceleration_limit = (2. * cfl_number * radius / acceleration).sqrt();
+    velocity_limit.min(acceleration_limit)
+}
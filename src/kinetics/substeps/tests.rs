@@ -0,0 +1,22 @@
+#[cfg(test)]
+mod substeps_tests {
+    use super::super::*;
+
+    #[test]
+    fn substep_covers_the_allowed_distance() {
+        let (cfl_number, radius) = (0.5, 4.);
+        let allowed_distance = cfl_number * radius / PIXELS_PER_METER;
+
+        let delta = cfl_delta(cfl_number, radius, 2., 0.);
+        assert!((2. * delta - allowed_distance).abs() < 1e-6, "{delta}");
+
+        let acceleration = 30.;
+        let delta = cfl_delta(cfl_number, radius, 0., acceleration);
+        assert!(
+            (0.5 * acceleration * delta * delta - allowed_distance).abs() < 1e-6,
+            "{delta}"
+        );
+
+        assert_eq!(cfl_delta(cfl_number, radius, 0., 0.), f32::INFINITY);
+    }
+}
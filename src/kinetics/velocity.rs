@@ -1,21 +1,32 @@
 use bevy::prelude::*;
 
-use super::bounds::{MAX_X, MAX_Y, MIN_X, MIN_Y};
+use super::bounds::periodic::Periodicity;
 
 #[derive(Component, Clone)]
 pub struct Velocity(pub Vec2);
 
-pub fn move_entities(time: Res<Time>, mut query: Query<(&Velocity, &mut Transform)>) -> () {
+pub fn move_entities(
+    time: Res<Time>,
+    periodicity: Res<Periodicity>,
+    mut query: Query<(&Velocity, &mut Transform)>,
+) {
     query
         .par_iter_mut()
         .for_each(|(Velocity(velocity), mut transform)| {
-            transform.translation = (transform.translation
-                + Vec3::from((*velocity, 0.)) * time.delta().as_secs_f32() * PIXELS_PER_METER)
-                .clamp(
-                    Vec3::new(MIN_X, MIN_Y, f32::MIN),
-                    Vec3::new(MAX_X-1., MAX_Y-1., f32::MAX),
-                );
+            displace(
+                &mut transform,
+                velocity * time.delta().as_secs_f32(),
+                &periodicity,
+            );
         });
 }
 
-const PIXELS_PER_METER: f32 = 40.;
+/// Moves `transform` by `displacement` meters. Along periodic axes the
+/// position is wrapped, so that the particles cross the seam; the walls are
+/// left to `enforce_bounds`.
+pub fn displace(transform: &mut Transform, displacement: Vec2, periodicity: &Periodicity) {
+    let position = periodicity.wrap(transform.translation.xy() + displacement * PIXELS_PER_METER);
+    transform.translation = position.extend(transform.translation.z);
+}
+
+pub const PIXELS_PER_METER: f32 = 40.;
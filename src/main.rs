@@ -7,22 +7,29 @@ mod performance_monitor;
 mod kinetics;
 mod particles_counter;
 mod controls;
+mod sources;
 
 fn main() {
+    // Reproducible steps, see `DeterministicMode`.
+    let deterministic = std::env::args().any(|arg| arg == "--deterministic");
     App::new()
         .add_plugins((
             DefaultPlugins,
             controls::ControlsPlugin,
-            KineticsPlugin,
+            KineticsPlugin {
+                deterministic,
+                ..default()
+            },
             draw::DrawPlugin,
             performance_monitor::PerformanceMonitorPlugin,
             particles_counter::ParticlesCounterPlugin,
+            sources::SourcesPlugin,
         ))
         .add_systems(Startup, spawn_camera)
         .insert_resource(Time::<Fixed>::from_hz(144.))
         .run();
 }
 
-fn spawn_camera(mut commands: Commands) -> () {
+fn spawn_camera(mut commands: Commands) {
     commands.spawn(Camera2d);
 }
@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::fluids::particle::FluidParticle;
+use crate::{fluids::particle::FluidParticle, sources::ParticleFlow};
 
 pub struct ParticlesCounterPlugin;
 
@@ -40,11 +40,15 @@ fn spawn_counter(mut commands: Commands) {
 
 fn update_counter(
     particles_q: Query<&FluidParticle>,
+    particle_flow: Res<ParticleFlow>,
     mut text_q: Query<&mut TextSpan, With<CounterText>>,
 ) {
     let amount = particles_q.iter().count();
     let mut span = text_q.single_mut();
-    **span = format!("{}", amount);
+    **span = format!(
+        "{} (+{} -{})",
+        amount, particle_flow.emitted, particle_flow.drained
+    );
 }
 
 #[derive(Component)]
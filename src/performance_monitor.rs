@@ -5,6 +5,11 @@ use bevy::{
     prelude::*,
 };
 
+use crate::kinetics::{
+    determinism::{DeterministicMode, StateChecksum},
+    limiter::MotionLimits,
+};
+
 pub struct PerformanceMonitorPlugin;
 
 impl Plugin for PerformanceMonitorPlugin {
@@ -18,6 +23,9 @@ impl Plugin for PerformanceMonitorPlugin {
                     update_collision_detection_duration,
                     update_collision_detection_checked_pairs,
                     update_collision_detection_colliding_pairs,
+                    update_time_step,
+                    update_checksum,
+                    update_limiter,
                 ),
             );
     }
@@ -122,11 +130,91 @@ fn spawn_monitor(mut commands: Commands) {
             CollisionDetectionCollidingPairsText,
         ));
 
+    commands
+        .spawn((
+            Text::new("Time step: "),
+            TextFont {
+                font_size: 32.,
+                ..default()
+            },
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(160.),
+                left: Val::Px(5.),
+                ..default()
+            },
+        ))
+        .with_child((
+            (
+                TextSpan::default(),
+                TextFont {
+                    font_size: 32.,
+                    ..default()
+                },
+            ),
+            TimeStepText,
+        ));
+
+    commands
+        .spawn((
+            Text::new("Checksum: "),
+            TextFont {
+                font_size: 32.,
+                ..default()
+            },
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(200.),
+                left: Val::Px(5.),
+                ..default()
+            },
+        ))
+        .with_child((
+            (
+                TextSpan::default(),
+                TextFont {
+                    font_size: 32.,
+                    ..default()
+                },
+            ),
+            ChecksumText,
+        ));
+
+    commands
+        .spawn((
+            Text::new("Clamped: "),
+            TextFont {
+                font_size: 32.,
+                ..default()
+            },
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(240.),
+                left: Val::Px(5.),
+                ..default()
+            },
+        ))
+        .with_child((
+            (
+                TextSpan::default(),
+                TextFont {
+                    font_size: 32.,
+                    ..default()
+                },
+            ),
+            LimiterText,
+        ));
+
     commands.insert_resource(CollisionDetectionMonitor {
         duration: Duration::new(0, 0),
         checked_pairs: 0,
         colliding_pairs: 0,
-    })
+    });
+    commands.insert_resource(TimeStepMonitor {
+        delta: Duration::new(0, 0),
+        substeps: 1,
+    });
+    commands.insert_resource(LimiterMonitor::default());
 }
 
 fn update_fps(diagnostics: Res<DiagnosticsStore>, mut query: Query<&mut TextSpan, With<FpsText>>) {
@@ -176,6 +264,49 @@ fn update_collision_detection_colliding_pairs(
     }
 }
 
+fn update_time_step(
+    time_step_monitor: Res<TimeStepMonitor>,
+    mut time_step_text_query: Query<&mut TextSpan, With<TimeStepText>>,
+) {
+    for mut span in &mut time_step_text_query {
+        **span = format!(
+            "{:?} x {}",
+            time_step_monitor.delta, time_step_monitor.substeps
+        );
+    }
+}
+
+fn update_checksum(
+    deterministic_mode: Res<DeterministicMode>,
+    state_checksum: Res<StateChecksum>,
+    mut checksum_text_query: Query<&mut TextSpan, With<ChecksumText>>,
+) {
+    for mut span in &mut checksum_text_query {
+        **span = if deterministic_mode.0 {
+            format!("{:016x} @ {}", state_checksum.value, state_checksum.step)
+        } else {
+            "off".to_string()
+        };
+    }
+}
+
+fn update_limiter(
+    motion_limits: Res<MotionLimits>,
+    limiter_monitor: Res<LimiterMonitor>,
+    mut limiter_text_query: Query<&mut TextSpan, With<LimiterText>>,
+) {
+    for mut span in &mut limiter_text_query {
+        **span = if motion_limits.enabled {
+            format!(
+                "{} acc / {} vel",
+                limiter_monitor.clamped_accelerations, limiter_monitor.clamped_velocities
+            )
+        } else {
+            "off".to_string()
+        };
+    }
+}
+
 #[derive(Component)]
 struct FpsText;
 
@@ -186,6 +317,12 @@ struct CollisionDetectionDurationText;
 struct CollisionDetectionCheckedPairsText;
 #[derive(Component)]
 struct CollisionDetectionCollidingPairsText;
+#[derive(Component)]
+struct TimeStepText;
+#[derive(Component)]
+struct ChecksumText;
+#[derive(Component)]
+struct LimiterText;
 
 #[derive(Resource)]
 pub struct CollisionDetectionMonitor {
@@ -193,3 +330,18 @@ pub struct CollisionDetectionMonitor {
     pub checked_pairs: usize,
     pub colliding_pairs: usize,
 }
+
+/// Length and amount of the substeps the last `FixedUpdate` was split into.
+#[derive(Resource)]
+pub struct TimeStepMonitor {
+    pub delta: Duration,
+    pub substeps: usize,
+}
+
+/// Most particles whose acceleration or velocity was clamped at once during
+/// the last `FixedUpdate`.
+#[derive(Resource, Default)]
+pub struct LimiterMonitor {
+    pub clamped_accelerations: usize,
+    pub clamped_velocities: usize,
+}
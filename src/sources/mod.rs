@@ -0,0 +1,205 @@
+mod tests;
+
+use std::collections::BTreeSet;
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    draw::spawn_particle,
+    fluids::{
+        kinds::{FluidKind, FluidKinds},
+        particle::FluidParticle,
+    },
+    kinetics::{
+        collisions::position_hashing::PositionHashMap, obstacles::shape::ObstacleShape, substeps,
+        Solver,
+    },
+};
+
+pub struct SourcesPlugin;
+
+impl Plugin for SourcesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ParticleFlow>()
+            .add_systems(
+                Startup,
+                spawn_sources.run_if(not(resource_equals(Solver::StableFluids))),
+            )
+            .add_systems(Update, draw_sources)
+            .add_systems(
+                FixedUpdate,
+                (drain_particles, emit_particles)
+                    .chain()
+                    .before(substeps::run_substeps),
+            );
+    }
+}
+
+/// Spawns particles of `kind` from its `Transform`, spread along a segment
+/// of `width` pixels across `velocity`. It stays idle while the phases don't
+/// have its kind.
+#[derive(Component, Clone)]
+pub struct Emitter {
+    pub kind: FluidKind,
+    /// Radius of the emitted particles, in pixels.
+    pub radius: f32,
+    /// Particles per second.
+    pub rate: f32,
+    /// Velocity of the emitted particles, in m/s.
+    pub velocity: Vec2,
+    /// Largest angle, in radians, between `velocity` and the velocity of an
+    /// emitted particle.
+    pub spread: f32,
+    pub width: f32,
+    /// Fraction of a particle left over from the previous steps.
+    pending: f32,
+    rng: StdRng,
+}
+
+impl Emitter {
+    /// `seed` drives the offsets, angles and colours of the emitted
+    /// particles, so every emitter should get its own.
+    pub fn new(kind: FluidKind, rate: f32, velocity: Vec2, seed: u64) -> Emitter {
+        Emitter {
+            kind,
+            radius: 3.,
+            rate,
+            velocity,
+            spread: 0.,
+            width: 0.,
+            pending: 0.,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+/// Region, placed by its `Transform`, despawning the particles whose center
+/// enters it.
+#[derive(Component, Clone, Debug)]
+pub struct Sink {
+    pub shape: ObstacleShape,
+}
+
+/// Amount of particles that went through the emitters and the sinks.
+#[derive(Resource, Default)]
+pub struct ParticleFlow {
+    pub emitted: usize,
+    pub drained: usize,
+}
+
+fn spawn_sources(mut commands: Commands) {
+    let mut emitter = Emitter::new(FluidKind::default(), 150., Vec2::new(4., -1.), 40);
+    emitter.spread = 0.15;
+    emitter.width = 12.;
+    commands.spawn((emitter, Transform::from_xyz(-185., 170., 0.)));
+    commands.spawn((
+        Sink {
+            shape: ObstacleShape::Rectangle {
+                half_size: Vec2::new(15., 15.),
+            },
+        },
+        Transform::from_xyz(180., -180., 0.),
+    ));
+}
+
+fn draw_sources(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    emitters_q: Query<Entity, Added<Emitter>>,
+    sinks_q: Query<(Entity, &Sink), Added<Sink>>,
+) {
+    for entity in emitters_q.iter() {
+        commands.entity(entity).insert((
+            Mesh2d(meshes.add(Circle::new(4.))),
+            MeshMaterial2d(materials.add(Color::hsla(120., 0.8, 0.5, 0.6))),
+        ));
+    }
+    for (entity, sink) in sinks_q.iter() {
+        commands.entity(entity).insert((
+            Mesh2d(meshes.add(&sink.shape)),
+            MeshMaterial2d(materials.add(Color::hsla(0., 0.8, 0.5, 0.3))),
+        ));
+    }
+}
+
+pub fn emit_particles(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut particle_flow: ResMut<ParticleFlow>,
+    fluid_kinds: Res<FluidKinds>,
+    mut emitters_q: Query<(&mut Emitter, &Transform)>,
+) {
+    for (mut emitter, transform) in emitters_q.iter_mut() {
+        let Some(material) = fluid_kinds.material(emitter.kind) else {
+            emitter.pending = 0.;
+            continue;
+        };
+        let (particle, mass) = material.particle(emitter.radius);
+        emitter.pending += emitter.rate * time.delta().as_secs_f32();
+        let across = emitter.velocity.normalize_or_zero().perp();
+        while emitter.pending >= 1. {
+            emitter.pending -= 1.;
+            let Emitter {
+                kind,
+                velocity,
+                spread,
+                width,
+                rng,
+                ..
+            } = emitter.as_mut();
+            let offset = across * rng.gen_range(-0.5..=0.5) * *width;
+            let angle = rng.gen_range(-*spread..=*spread);
+            let color = material
+                .color
+                .unwrap_or_else(|| Color::hsl(rng.gen_range(0.0..360.), 0.95, 0.7));
+            spawn_particle(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                particle,
+                *kind,
+                mass,
+                transform.translation.xy() + offset,
+                Vec2::from_angle(angle).rotate(*velocity),
+                color,
+            );
+            particle_flow.emitted += 1;
+        }
+    }
+}
+
+pub fn drain_particles(
+    mut commands: Commands,
+    position_hash_map: Res<PositionHashMap>,
+    mut particle_flow: ResMut<ParticleFlow>,
+    sinks_q: Query<(&Sink, &Transform)>,
+    particles_q: Query<&Transform, With<FluidParticle>>,
+) {
+    // A particle inside several sinks is only drained once. The set is
+    // sorted so that the despawned entities are recycled in the same order
+    // on every run.
+    let mut drained = BTreeSet::new();
+    for (sink, sink_transform) in sinks_q.iter() {
+        for entity in position_hash_map.entities_near(
+            sink_transform.translation.xy(),
+            sink.shape.bounding_radius(),
+        ) {
+            let Ok(transform) = particles_q.get(entity) else {
+                continue;
+            };
+            let local_center = sink_transform.rotation.inverse()
+                * (transform.translation - sink_transform.translation);
+            if sink.shape.signed_distance(local_center.xy()).0 < 0. {
+                drained.insert(entity);
+            }
+        }
+    }
+    particle_flow.drained += drained.len();
+    for entity in drained {
+        commands.entity(entity).despawn();
+    }
+}
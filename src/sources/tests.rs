@@ -0,0 +1,80 @@
+#[cfg(test)]
+mod sources_tests {
+    use std::time::Duration;
+
+    use super::super::*;
+    use crate::{
+        fluids::kinds::Phases,
+        kinetics::{
+            bounds::periodic::Periodicity,
+            collisions::position_hashing::{update_position_map, PositionHashingPlugin},
+            mass::Mass,
+        },
+    };
+
+    #[test]
+    fn particle_inside_overlapping_sinks_is_drained_once() {
+        let mut app = App::new();
+        app.add_plugins(PositionHashingPlugin)
+            .insert_resource(Periodicity::default())
+            .init_resource::<ParticleFlow>();
+        app.world_mut().run_schedule(Startup);
+
+        let world = app.world_mut();
+        for x in [0., 10.] {
+            world.spawn((
+                Sink {
+                    shape: ObstacleShape::Circle { radius: 20. },
+                },
+                Transform::from_xyz(x, 0., 0.),
+            ));
+        }
+        let particle = world
+            .spawn((
+                FluidParticle {
+                    radius: 3.,
+                    restitution_coeff: 1.,
+                    friction_coeff: 0.,
+                },
+                Transform::from_xyz(5., 0., 0.),
+            ))
+            .id();
+        world.run_system_cached(update_position_map).unwrap();
+        world.run_system_cached(drain_particles).unwrap();
+
+        assert_eq!(world.resource::<ParticleFlow>().drained, 1);
+        assert!(world.get_entity(particle).is_err());
+    }
+
+    #[test]
+    fn emitters_emit_their_kind_and_idle_without_it() {
+        let mut world = World::new();
+        world.init_resource::<Assets<Mesh>>();
+        world.init_resource::<Assets<ColorMaterial>>();
+        world.init_resource::<ParticleFlow>();
+        world.insert_resource(FluidKinds::new(Phases::OilAndWater));
+        let mut time = Time::<()>::default();
+        time.advance_by(Duration::from_secs_f32(0.1));
+        world.insert_resource(time);
+        world.spawn((
+            Emitter::new(FluidKind(1), 10., Vec2::X, 1),
+            Transform::default(),
+        ));
+
+        world.run_system_cached(emit_particles).unwrap();
+        let mut particles_q = world.query::<(&FluidKind, &Mass)>();
+        let oil = FluidKinds::new(Phases::OilAndWater)
+            .material(FluidKind(1))
+            .unwrap()
+            .particle_mass;
+        assert!(particles_q
+            .iter(&world)
+            .all(|(kind, mass)| *kind == FluidKind(1) && mass.0 == oil));
+        assert_eq!(world.resource::<ParticleFlow>().emitted, 1);
+
+        // Water alone has no second kind.
+        world.insert_resource(FluidKinds::new(Phases::Water));
+        world.run_system_cached(emit_particles).unwrap();
+        assert_eq!(world.resource::<ParticleFlow>().emitted, 1);
+    }
+}